@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
 
 use anyhow::{anyhow, Result};
@@ -10,22 +11,26 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    config::{proxy::ReqClient, Account, APP_VERSION},
-    ui::{models::emby_cache_path, widgets::single_grid::imp::ListType},
+    client::device_profile::{device_profile, DEFAULT_MAX_STREAMING_BITRATE},
+    client::outbox::OUTBOX,
+    config::{proxy::ReqClient, update_account, Account, APP_VERSION},
+    ui::{
+        models::{emby_cache_path, texture_cache::TEXTURE_CACHE},
+        widgets::single_grid::imp::ListType,
+    },
     utils::{spawn, spawn_tokio},
 };
 
 use once_cell::sync::Lazy;
 
 use super::structs::{
-    ActivityLogs, AuthenticateResponse, Back, ExternalIdInfo, ImageItem, Item, List, LiveMedia,
-    LoginResponse, Media, RemoteSearchInfo, RemoteSearchResult, ScheduledTask, SerInList,
-    ServerInfo, SimpleListItem,
+    ActivityLogs, AuthenticateResponse, Back, ChapterInfo, ExternalIdInfo, ImageItem, Item, List,
+    LiveMedia, LoginResponse, Media, MediaAttachment, RemoteSearchInfo, RemoteSearchResult,
+    RemoteSubtitleInfo, ScheduledTask, SerInList, ServerInfo, SimpleListItem, TrickplayInfo,
 };
 
 pub static EMBY_CLIENT: Lazy<EmbyClient> = Lazy::new(EmbyClient::default);
 pub static DEVICE_ID: Lazy<String> = Lazy::new(|| Uuid::new_v4().to_string());
-static PROFILE: &str = include_str!("stream_profile.json");
 static LIVEPROFILE: &str = include_str!("test.json");
 static CLIENT_ID: Lazy<String> = Lazy::new(|| "Tsukimi".to_string());
 static DEVICE_NAME: Lazy<String> = Lazy::new(|| {
@@ -35,13 +40,33 @@ static DEVICE_NAME: Lazy<String> = Lazy::new(|| {
         .to_string()
 });
 
-#[derive(PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum BackType {
     Start,
     Stop,
     Back,
 }
 
+/// Ways to have the server prepare a stream the player couldn't open directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamFallback {
+    /// Repackage the original streams into HLS
+    Remux,
+    /// Re-encode audio and video
+    Transcode,
+}
+
+impl StreamFallback {
+    /// The next fallback to try after `current` failed, `None` meaning direct streaming.
+    pub fn after(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(StreamFallback::Remux),
+            Some(StreamFallback::Remux) => Some(StreamFallback::Transcode),
+            Some(StreamFallback::Transcode) => None,
+        }
+    }
+}
+
 pub struct EmbyClient {
     pub url: Mutex<Option<Url>>,
     pub client: reqwest::Client,
@@ -51,6 +76,8 @@ pub struct EmbyClient {
     pub user_password: Mutex<String>,
     pub user_access_token: Mutex<String>,
     pub server_name: Mutex<String>,
+    pub max_bitrate: Mutex<u64>,
+    pub max_bitrate_metered: Mutex<u64>,
 }
 
 impl EmbyClient {
@@ -80,6 +107,8 @@ impl EmbyClient {
             user_password: Mutex::new(String::new()),
             user_access_token: Mutex::new(String::new()),
             server_name: Mutex::new(String::new()),
+            max_bitrate: Mutex::new(0),
+            max_bitrate_metered: Mutex::new(0),
         }
     }
 
@@ -91,6 +120,11 @@ impl EmbyClient {
         self.set_user_password(&account.password)?;
         self.set_user_access_token(&account.access_token)?;
         self.set_server_name(&account.servername)?;
+        *self.max_bitrate.lock().unwrap() = account.max_bitrate;
+        *self.max_bitrate_metered.lock().unwrap() = account.max_bitrate_metered;
+        // Item ids are only unique per server, so textures must not leak across accounts
+        TEXTURE_CACHE.clear();
+        OUTBOX.load();
         crate::ui::provider::set_admin(false);
         spawn(async move {
             spawn_tokio(async move {
@@ -102,6 +136,9 @@ impl EmbyClient {
                     }
                     Err(e) => warn!("Failed to authenticate as admin: {}", e),
                 }
+                if !crate::ui::provider::is_offline() {
+                    let _ = OUTBOX.flush().await;
+                }
             })
             .await;
         });
@@ -245,6 +282,12 @@ impl EmbyClient {
         Ok(self.client.request(method, url).headers(headers))
     }
 
+    async fn request_bytes(&self, path: &str, params: &[(&str, &str)]) -> Result<Vec<u8>> {
+        let request = self.prepare_request(Method::GET, path, params)?;
+        let res = self.send_request(request).await?.error_for_status()?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
     async fn send_request(&self, request: RequestBuilder) -> Result<Response> {
         let res = request.send().await?;
         Ok(res)
@@ -308,12 +351,45 @@ impl EmbyClient {
         self.request(&path, &params).await
     }
 
+    /// Every episode of the series, in airing order across seasons.
+    pub async fn get_series_episodes(&self, id: &str) -> Result<SerInList> {
+        let path = format!("Shows/{}/Episodes", id);
+        let params = [
+            (
+                "Fields",
+                "Overview,PrimaryImageAspectRatio,PremiereDate,ProductionYear,SyncStatus",
+            ),
+            ("ImageTypeLimit", "1"),
+            ("UserId", &self.user_id()),
+        ];
+        self.request(&path, &params).await
+    }
+
     pub async fn get_item_info(&self, id: &str) -> Result<Item> {
         let path = format!("Users/{}/Items/{}", self.user_id(), id);
         let params = [("Fields", "ShareLevel")];
         self.request(&path, &params).await
     }
 
+    pub async fn get_chapters(&self, id: &str) -> Result<Vec<ChapterInfo>> {
+        let path = format!("Users/{}/Items/{}", self.user_id(), id);
+        let params = [("Fields", "Chapters")];
+        let item: Item = self.request(&path, &params).await?;
+        Ok(item.chapters.unwrap_or_default())
+    }
+
+    /// The library `id` is in, if the server tells.
+    pub async fn get_library_id(&self, id: &str) -> Result<Option<String>> {
+        let path = format!("Items/{}/Ancestors", id);
+        let user_id = self.user_id();
+        let params = [("UserId", user_id.as_str())];
+        let ancestors: Vec<SimpleListItem> = self.request(&path, &params).await?;
+        Ok(ancestors
+            .into_iter()
+            .find(|ancestor| ancestor.latest_type == "CollectionFolder")
+            .map(|ancestor| ancestor.id))
+    }
+
     pub async fn get_edit_info(&self, id: &str) -> Result<Item> {
         let path = format!("Users/{}/Items/{}", self.user_id(), id);
         let params = [("Fields", "ChannelMappingInfo")];
@@ -344,7 +420,7 @@ impl EmbyClient {
         &self,
         id: &str,
         image_type: &str,
-        tag: Option<u8>,
+        tag: Option<u32>,
     ) -> Result<Response> {
         let mut path = format!("Items/{}/Images/{}", id, image_type);
         if let Some(tag) = tag {
@@ -371,7 +447,7 @@ impl EmbyClient {
         self.request_picture(&path, &params).await
     }
 
-    pub async fn get_image(&self, id: &str, image_type: &str, tag: Option<u8>) -> Result<String> {
+    pub async fn get_image(&self, id: &str, image_type: &str, tag: Option<u32>) -> Result<String> {
         match self.image_request(id, image_type, tag).await {
             Ok(response) => {
                 let bytes = response.bytes().await?;
@@ -388,7 +464,7 @@ impl EmbyClient {
         }
     }
 
-    pub fn save_image(&self, id: &str, image_type: &str, tag: Option<u8>, bytes: &[u8]) -> String {
+    pub fn save_image(&self, id: &str, image_type: &str, tag: Option<u32>, bytes: &[u8]) -> String {
         let cache_path = emby_cache_path();
         let path = format!("{}-{}-{}", id, image_type, tag.unwrap_or(0));
         let path = cache_path.join(path);
@@ -428,8 +504,44 @@ impl EmbyClient {
         self.request(&path, &params).await
     }
 
+    /// Bitrate limit the user picked for the current kind of network, 0 for no limit.
+    pub fn streaming_bitrate_limit(&self) -> u64 {
+        if crate::ui::provider::is_metered() {
+            *self.max_bitrate_metered.lock().unwrap()
+        } else {
+            *self.max_bitrate.lock().unwrap()
+        }
+    }
+
+    /// Sets the bitrate limit for the current kind of network and saves it to the account.
+    pub fn set_streaming_bitrate_limit(&self, bitrate: u64) -> Result<()> {
+        let metered = crate::ui::provider::is_metered();
+        if metered {
+            *self.max_bitrate_metered.lock().unwrap() = bitrate;
+        } else {
+            *self.max_bitrate.lock().unwrap() = bitrate;
+        }
+        let server_name = self.server_name.lock().unwrap().clone();
+        update_account(&server_name, &self.user_id(), |account| {
+            if metered {
+                account.max_bitrate_metered = bitrate;
+            } else {
+                account.max_bitrate = bitrate;
+            }
+        })
+        .map_err(|e| anyhow!("Failed to save bitrate: {}", e))
+    }
+
+    fn max_streaming_bitrate(&self) -> u64 {
+        match self.streaming_bitrate_limit() {
+            0 => DEFAULT_MAX_STREAMING_BITRATE,
+            bitrate => bitrate,
+        }
+    }
+
     pub async fn get_playbackinfo(&self, id: &str) -> Result<Media> {
         let path = format!("Items/{}/PlaybackInfo", id);
+        let max_bitrate = self.max_streaming_bitrate();
         let params = [
             ("StartTimeTicks", "0"),
             ("UserId", &self.user_id()),
@@ -437,11 +549,54 @@ impl EmbyClient {
             ("IsPlayback", "false"),
             ("AudioStreamIndex", "1"),
             ("SubtitleStreamIndex", "1"),
-            ("MaxStreamingBitrate", "160000000"),
+            ("MaxStreamingBitrate", &max_bitrate.to_string()),
             ("reqformat", "json"),
         ];
-        let profile: Value = serde_json::from_str(PROFILE).expect("Failed to parse profile");
-        self.post_json(&path, &params, profile).await
+        self.post_json(&path, &params, device_profile(max_bitrate))
+            .await
+    }
+
+    /// Requests a server-prepared stream of one media source when direct streaming failed.
+    pub async fn get_fallback_playbackinfo(
+        &self,
+        id: &str,
+        media_source_id: &str,
+        fallback: StreamFallback,
+        audio_stream_index: Option<i64>,
+        subtitle_stream_index: Option<i64>,
+    ) -> Result<Media> {
+        let path = format!("Items/{}/PlaybackInfo", id);
+        let max_bitrate = self.max_streaming_bitrate();
+        let max_bitrate_string = max_bitrate.to_string();
+        let user_id = self.user_id();
+        let stream_copy = match fallback {
+            StreamFallback::Remux => "true",
+            StreamFallback::Transcode => "false",
+        };
+        let mut params = vec![
+            ("StartTimeTicks", "0"),
+            ("UserId", &user_id),
+            ("AutoOpenLiveStream", "true"),
+            ("IsPlayback", "true"),
+            ("MediaSourceId", media_source_id),
+            ("MaxStreamingBitrate", &max_bitrate_string),
+            ("EnableDirectPlay", "false"),
+            ("EnableDirectStream", "false"),
+            ("EnableTranscoding", "true"),
+            ("AllowVideoStreamCopy", stream_copy),
+            ("AllowAudioStreamCopy", stream_copy),
+            ("reqformat", "json"),
+        ];
+        let audio_stream_index = audio_stream_index.map(|index| index.to_string());
+        if let Some(index) = &audio_stream_index {
+            params.push(("AudioStreamIndex", index));
+        }
+        let subtitle_stream_index = subtitle_stream_index.map(|index| index.to_string());
+        if let Some(index) = &subtitle_stream_index {
+            params.push(("SubtitleStreamIndex", index));
+        }
+        self.post_json(&path, &params, device_profile(max_bitrate))
+            .await
     }
 
     pub async fn scan(&self, id: &str) -> Result<Response> {
@@ -483,6 +638,71 @@ impl EmbyClient {
         self.post_json(&path, &[], body).await
     }
 
+    pub async fn search_remote_subtitles(
+        &self,
+        id: &str,
+        language: &str,
+        media_source_id: &str,
+    ) -> Result<Vec<RemoteSubtitleInfo>> {
+        let path = format!("Items/{}/RemoteSearch/Subtitles/{}", id, language);
+        let params = [("MediaSourceId", media_source_id)];
+        self.request(&path, &params).await
+    }
+
+    /// Has the server download a subtitle found by `search_remote_subtitles` next to the
+    /// item.
+    pub async fn download_remote_subtitle(&self, id: &str, subtitle_id: &str) -> Result<()> {
+        let path = format!("Items/{}/RemoteSearch/Subtitles/{}", id, subtitle_id);
+        self.post(&path, &[], json!({})).await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Downloads a file embedded in a media source, such as a subtitle font.
+    pub async fn get_attachment(
+        &self,
+        id: &str,
+        media_source_id: &str,
+        attachment: &MediaAttachment,
+    ) -> Result<Vec<u8>> {
+        let path = attachment.delivery_url.clone().unwrap_or_else(|| {
+            format!(
+                "Videos/{}/{}/Attachments/{}",
+                id, media_source_id, attachment.index
+            )
+        });
+        self.request_bytes(path.trim_start_matches('/'), &[]).await
+    }
+
+    /// Trickplay tile sheet resolutions per media source, from servers that make them.
+    pub async fn get_trickplay_info(
+        &self,
+        id: &str,
+    ) -> Result<HashMap<String, HashMap<String, TrickplayInfo>>> {
+        let path = format!("Users/{}/Items/{}", self.user_id(), id);
+        let params = [("Fields", "Trickplay")];
+        let item: Item = self.request(&path, &params).await?;
+        Ok(item.trickplay.unwrap_or_default())
+    }
+
+    pub async fn get_trickplay_tile(
+        &self,
+        id: &str,
+        media_source_id: &str,
+        width: u32,
+        index: u32,
+    ) -> Result<Vec<u8>> {
+        let path = format!("Videos/{}/Trickplay/{}/{}.jpg", id, width, index);
+        let params = [("MediaSourceId", media_source_id)];
+        self.request_bytes(&path, &params).await
+    }
+
+    /// The BIF thumbnail index Emby makes for seeking previews.
+    pub async fn get_bif(&self, id: &str, media_source_id: &str) -> Result<Vec<u8>> {
+        let path = format!("Videos/{}/index.bif", id);
+        let params = [("Width", "320"), ("MediaSourceId", media_source_id)];
+        self.request_bytes(&path, &params).await
+    }
+
     pub async fn get_external_id_info(&self, id: &str) -> Result<Vec<ExternalIdInfo>> {
         let path = format!("Items/{}/ExternalIdInfos", id);
         let params = [("IsSupportedAsIdentifier", "true")];
@@ -496,7 +716,10 @@ impl EmbyClient {
             ("UserId", &self.user_id()),
             ("AutoOpenLiveStream", "true"),
             ("IsPlayback", "true"),
-            ("MaxStreamingBitrate", "160000000"),
+            (
+                "MaxStreamingBitrate",
+                &self.max_streaming_bitrate().to_string(),
+            ),
             ("reqformat", "json"),
         ];
         let profile: Value = serde_json::from_str(LIVEPROFILE).unwrap();
@@ -516,8 +739,8 @@ impl EmbyClient {
             ("MaxStreamingBitrate", "4000000"),
             ("reqformat", "json"),
         ];
-        let profile: Value = serde_json::from_str(PROFILE).unwrap();
-        self.post_json(&path, &params, profile).await
+        self.post_json(&path, &params, device_profile(4000000))
+            .await
     }
 
     pub async fn get_library(&self) -> Result<List> {
@@ -706,7 +929,13 @@ impl EmbyClient {
             BackType::Back => "Sessions/Playing/Progress".to_string(),
         };
         let params = [("reqformat", "json")];
-        let body = json!({"VolumeLevel":100,"IsMuted":false,"IsPaused":false,"RepeatMode":"RepeatNone","SubtitleOffset":0,"PlaybackRate":1,"MaxStreamingBitrate": 400000000u64,"PositionTicks":back.tick,"PlaybackStartTimeTicks":0,"SubtitleStreamIndex":1,"AudioStreamIndex":1,"BufferedRanges":[],"PlayMethod":"DirectStream","PlaySessionId":back.playsessionid,"MediaSourceId":back.mediasourceid,"CanSeek":true,"ItemId":back.id,"PlaylistIndex":0,"PlaylistLength":23,"NextMediaType":"Video"});
+        let state = &back.state;
+        let buffered_ranges: Vec<Value> = state
+            .buffered_ranges
+            .iter()
+            .map(|(start, end)| json!({"start": start, "end": end}))
+            .collect();
+        let body = json!({"VolumeLevel":state.volume,"IsMuted":state.is_muted,"IsPaused":state.is_paused,"RepeatMode":"RepeatNone","SubtitleOffset":0,"PlaybackRate":state.speed,"MaxStreamingBitrate": 400000000u64,"PositionTicks":back.tick,"PlaybackStartTimeTicks":0,"SubtitleStreamIndex":state.subtitle_stream_index.unwrap_or(-1),"AudioStreamIndex":state.audio_stream_index,"BufferedRanges":buffered_ranges,"PlayMethod":state.play_method,"PlaySessionId":back.playsessionid,"MediaSourceId":back.mediasourceid,"CanSeek":true,"ItemId":back.id,"PlaylistIndex":state.playlist_index,"PlaylistLength":state.playlist_length,"NextMediaType":"Video"});
         self.post(&path, &params, body).await?;
         Ok(())
     }
@@ -844,6 +1073,42 @@ impl EmbyClient {
         self.request(&path, &params).await
     }
 
+    /// A page of the videos under `parent_id` matching `filter`, e.g. `("GenreIds", id)`.
+    /// Without `sort_by` the server's own order is kept, as box sets and playlists need.
+    pub async fn get_video_queue(
+        &self,
+        parent_id: Option<&str>,
+        filter: Option<(&str, &str)>,
+        sort_by: Option<&str>,
+        start: u32,
+    ) -> Result<List> {
+        let path = format!("Users/{}/Items", &self.user_id());
+        let start_string = start.to_string();
+        let mut params = vec![
+            ("Limit", "100"),
+            (
+                "Fields",
+                "BasicSyncInfo,CanDelete,PrimaryImageAspectRatio,ProductionYear",
+            ),
+            ("ImageTypeLimit", "1"),
+            ("StartIndex", &start_string),
+            ("Recursive", "true"),
+            ("IncludeItemTypes", "Movie,Episode,Video,MusicVideo"),
+            ("EnableImageTypes", "Primary,Backdrop,Thumb"),
+        ];
+        if let Some(parent_id) = parent_id {
+            params.push(("ParentId", parent_id));
+        }
+        if let Some(filter) = filter {
+            params.push(filter);
+        }
+        if let Some(sort_by) = sort_by {
+            params.push(("SortBy", sort_by));
+            params.push(("SortOrder", "Ascending"));
+        }
+        self.request(&path, &params).await
+    }
+
     pub async fn get_includedby(&self, parent_id: &str) -> Result<List> {
         let path = format!("Users/{}/Items", &self.user_id());
         let params = [
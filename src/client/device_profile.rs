@@ -0,0 +1,147 @@
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+static PROFILE: &str = include_str!("stream_profile.json");
+
+/// Bitrate requested when the user hasn't limited it.
+pub const DEFAULT_MAX_STREAMING_BITRATE: u64 = 160000000;
+
+const VIDEO_CODECS: &[&str] = &[
+    "h264",
+    "hevc",
+    "av1",
+    "vp8",
+    "vp9",
+    "h263",
+    "mpeg1video",
+    "mpeg2video",
+    "mpeg4",
+    "msmpeg4v3",
+    "vc1",
+    "wmv3",
+    "theora",
+    "prores",
+];
+
+const AUDIO_CODECS: &[&str] = &[
+    "aac",
+    "mp1",
+    "mp2",
+    "mp3",
+    "ac3",
+    "eac3",
+    "truehd",
+    "mlp",
+    "dts",
+    "flac",
+    "alac",
+    "opus",
+    "vorbis",
+    "wmav2",
+    "pcm_s8",
+    "pcm_s16le",
+    "pcm_s16be",
+    "pcm_s24le",
+    "pcm_s32le",
+    "pcm_f32le",
+    "pcm_alaw",
+    "pcm_mulaw",
+];
+
+// Other names Emby uses for a codec ffmpeg knows by the first name
+const CODEC_ALIASES: &[(&str, &[&str])] = &[
+    ("hevc", &["hev1", "dvhe", "dvh1"]),
+    ("dts", &["dca"]),
+    ("pcm_s16le", &["pcm"]),
+];
+
+// Emby container names each lavf demuxer can open
+const CONTAINERS: &[(&str, &[&str])] = &[
+    ("matroska", &["mkv", "webm"]),
+    ("webm", &["webm"]),
+    ("mov", &["mov", "mp4", "m4v", "3gp", "fmp4"]),
+    ("mp4", &["mp4", "m4v"]),
+    ("mpegts", &["ts", "mpegts", "m2ts"]),
+    ("avi", &["avi"]),
+    ("flv", &["flv"]),
+    ("ogg", &["ogg", "ogv"]),
+    ("asf", &["asf", "wmv"]),
+    ("mpeg", &["mpeg", "mpg", "vob"]),
+    ("hls", &["hls"]),
+    ("wav", &["wav"]),
+    ("flac", &["flac"]),
+    ("mp3", &["mp3"]),
+    ("aac", &["aac"]),
+];
+
+/// Codecs and demuxers the local player supports, as reported by mpv.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerCapabilities {
+    pub decoders: Vec<String>,
+    pub demuxers: Vec<String>,
+}
+
+static CAPABILITIES: Lazy<RwLock<Option<PlayerCapabilities>>> = Lazy::new(|| RwLock::new(None));
+
+pub fn set_player_capabilities(capabilities: PlayerCapabilities) {
+    *CAPABILITIES.write().unwrap() = Some(capabilities);
+}
+
+/// Builds the device profile sent with PlaybackInfo requests.
+///
+/// Direct play is advertised for what the player reported it can decode, falling back to
+/// the bundled profile until the player is up. Bitrates above `max_bitrate` are transcoded.
+pub fn device_profile(max_bitrate: u64) -> Value {
+    let mut profile: Value = serde_json::from_str(PROFILE).expect("Failed to parse profile");
+    let device_profile = &mut profile["DeviceProfile"];
+
+    if let Some(capabilities) = CAPABILITIES.read().unwrap().as_ref() {
+        let video_codecs = supported_codecs(VIDEO_CODECS, &capabilities.decoders);
+        let audio_codecs = supported_codecs(AUDIO_CODECS, &capabilities.decoders);
+        let containers = supported_containers(&capabilities.demuxers);
+        if !video_codecs.is_empty() && !containers.is_empty() {
+            device_profile["DirectPlayProfiles"] = json!([{
+                "Type": "Video",
+                "Container": containers.join(","),
+                "VideoCodec": video_codecs.join(","),
+                "AudioCodec": audio_codecs.join(","),
+            }]);
+        }
+    }
+
+    device_profile["MaxStreamingBitrate"] = json!(max_bitrate);
+    device_profile["MaxStaticBitrate"] = json!(max_bitrate);
+    profile
+}
+
+fn supported_codecs<'a>(candidates: &[&'a str], decoders: &[String]) -> Vec<&'a str> {
+    let mut codecs = Vec::new();
+    for codec in candidates {
+        if !decoders.iter().any(|decoder| decoder == codec) {
+            continue;
+        }
+        codecs.push(*codec);
+        if let Some((_, aliases)) = CODEC_ALIASES.iter().find(|(name, _)| name == codec) {
+            codecs.extend_from_slice(aliases);
+        }
+    }
+    codecs
+}
+
+fn supported_containers(demuxers: &[String]) -> Vec<&'static str> {
+    let mut containers = Vec::new();
+    // lavf lists demuxers with several names as "mov,mp4,m4a,3gp,3g2,mj2"
+    for name in demuxers.iter().flat_map(|demuxer| demuxer.split(',')) {
+        let Some((_, names)) = CONTAINERS.iter().find(|(demuxer, _)| *demuxer == name) else {
+            continue;
+        };
+        for container in names.iter() {
+            if !containers.contains(container) {
+                containers.push(*container);
+            }
+        }
+    }
+    containers
+}
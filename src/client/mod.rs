@@ -1,4 +1,6 @@
 pub mod client;
+pub mod device_profile;
 pub mod error;
 pub mod network;
+pub mod outbox;
 pub mod structs;
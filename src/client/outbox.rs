@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::warn;
+
+use super::client::{BackType, EMBY_CLIENT};
+use super::structs::Back;
+use crate::ui::models::emby_data_path;
+use crate::ui::provider::is_offline;
+
+pub static OUTBOX: Lazy<Outbox> = Lazy::new(Outbox::new);
+
+/// A write to the server that can be deferred until it is reachable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OutboxAction {
+    Like(String),
+    Unlike(String),
+    Played(String),
+    Unplayed(String),
+    HideFromResume(String),
+    Playback(Back, BackType),
+}
+
+impl OutboxAction {
+    async fn send(&self) -> Result<()> {
+        match self {
+            OutboxAction::Like(id) => EMBY_CLIENT.like(id).await,
+            OutboxAction::Unlike(id) => EMBY_CLIENT.unlike(id).await,
+            OutboxAction::Played(id) => EMBY_CLIENT.set_as_played(id).await,
+            OutboxAction::Unplayed(id) => EMBY_CLIENT.set_as_unplayed(id).await,
+            OutboxAction::HideFromResume(id) => EMBY_CLIENT.hide_from_resume(id).await,
+            OutboxAction::Playback(back, backtype) => {
+                EMBY_CLIENT.position_back(back, *backtype).await
+            }
+        }
+    }
+
+    /// Whether queueing `self` makes an earlier queued `other` pointless to replay.
+    fn supersedes(&self, other: &OutboxAction) -> bool {
+        use OutboxAction::*;
+        match (self, other) {
+            (Like(a) | Unlike(a), Like(b) | Unlike(b)) => a == b,
+            (Played(a) | Unplayed(a), Played(b) | Unplayed(b)) => a == b,
+            (HideFromResume(a), HideFromResume(b)) => a == b,
+            // Only the latest position of a session matters, and the stop report carries it too
+            (Playback(a, BackType::Back | BackType::Stop), Playback(b, BackType::Back)) => {
+                a.id == b.id && a.playsessionid == b.playsessionid
+            }
+            _ => false,
+        }
+    }
+}
+
+pub enum Delivery {
+    Sent,
+    Queued,
+}
+
+/// Persistent queue of server writes, replayed in order once the server is reachable.
+pub struct Outbox {
+    queue: Mutex<VecDeque<OutboxAction>>,
+    path: Mutex<Option<PathBuf>>,
+    pending: watch::Sender<usize>,
+    flushing: tokio::sync::Mutex<()>,
+}
+
+impl Outbox {
+    fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            path: Mutex::new(None),
+            pending: watch::Sender::new(0),
+            flushing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Loads the queue of the current account, replacing whatever was queued before.
+    pub fn load(&self) {
+        let path = emby_data_path().join(format!(
+            "outbox-{}.json",
+            EMBY_CLIENT.user_id.lock().unwrap()
+        ));
+        let queue = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        *self.queue.lock().unwrap() = queue;
+        self.path.lock().unwrap().replace(path);
+        self.notify();
+    }
+
+    fn save(&self) {
+        let Some(path) = self.path.lock().unwrap().clone() else {
+            return;
+        };
+        let queue = self.queue.lock().unwrap();
+        let result = if queue.is_empty() {
+            std::fs::remove_file(&path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            })
+        } else {
+            serde_json::to_string(&*queue)
+                .map_err(std::io::Error::other)
+                .and_then(|serialized| std::fs::write(&path, serialized))
+        };
+        if let Err(e) = result {
+            warn!("Failed to save outbox: {}", e);
+        }
+    }
+
+    fn notify(&self) {
+        self.pending.send_replace(self.pending_count());
+    }
+
+    fn push(&self, action: OutboxAction) {
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.retain(|queued| !action.supersedes(queued));
+            queue.push_back(action);
+        }
+        self.save();
+        self.notify();
+    }
+
+    /// Sends the action right away, or queues it while offline or when the server
+    /// can't be reached.
+    pub async fn submit(&self, action: OutboxAction) -> Result<Delivery> {
+        // Anything already waiting has to reach the server first
+        if is_offline() || self.pending_count() > 0 {
+            self.push(action);
+            if !is_offline() {
+                let _ = self.flush().await;
+            }
+            if self.pending_count() > 0 {
+                return Ok(Delivery::Queued);
+            }
+            return Ok(Delivery::Sent);
+        }
+
+        match action.send().await {
+            Ok(()) => Ok(Delivery::Sent),
+            Err(e) if is_connectivity_error(&e) => {
+                warn!("Server unreachable, queueing {:?}: {}", action, e);
+                self.push(action);
+                Ok(Delivery::Queued)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Queues the actions and gives them `timeout` to reach the server. Whatever isn't
+    /// sent in time stays queued on disk and is replayed by a later flush.
+    pub async fn submit_within(&self, actions: Vec<OutboxAction>, timeout: Duration) -> Delivery {
+        for action in actions {
+            self.push(action);
+        }
+        if !is_offline() {
+            match tokio::time::timeout(timeout, self.flush()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => warn!("Server unreachable, keeping queued actions: {}", e),
+                Err(_) => warn!("Timed out flushing the outbox"),
+            }
+        }
+        if self.pending_count() > 0 {
+            Delivery::Queued
+        } else {
+            Delivery::Sent
+        }
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    /// Notifies with the number of pending actions whenever the queue changes.
+    pub fn subscribe(&self) -> watch::Receiver<usize> {
+        self.pending.subscribe()
+    }
+
+    /// Replays queued actions in order, stopping at the first one the server can't
+    /// be reached for. Actions the server rejects are dropped so they can't block the queue.
+    pub async fn flush(&self) -> Result<usize> {
+        let _flushing = self.flushing.lock().await;
+        let mut sent = 0;
+        loop {
+            let Some(action) = self.queue.lock().unwrap().front().cloned() else {
+                break;
+            };
+            match action.send().await {
+                Ok(()) => sent += 1,
+                Err(e) if is_connectivity_error(&e) => return Err(e),
+                Err(e) => warn!("Dropping rejected {:?}: {}", action, e),
+            }
+            {
+                // A newer action may have superseded this one while it was being sent
+                let mut queue = self.queue.lock().unwrap();
+                if queue.front() == Some(&action) {
+                    queue.pop_front();
+                }
+            }
+            self.save();
+            self.notify();
+        }
+        Ok(sent)
+    }
+}
+
+fn is_connectivity_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>().is_some_and(|e| {
+        e.is_connect()
+            || e.is_timeout()
+            || e.status().is_some_and(|status| status.is_server_error())
+    })
+}
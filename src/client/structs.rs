@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
@@ -43,6 +45,12 @@ pub struct MediaStream {
     pub title: Option<String>,
     #[serde(rename = "DisplayLanguage")]
     pub display_language: Option<String>,
+    #[serde(rename = "Language")]
+    pub language: Option<String>,
+    #[serde(rename = "IsForced")]
+    pub is_forced: Option<bool>,
+    #[serde(rename = "IsHearingImpaired")]
+    pub is_hearing_impaired: Option<bool>,
     #[serde(rename = "Codec")]
     pub codec: Option<String>,
     #[serde(rename = "BitRate")]
@@ -83,8 +91,77 @@ pub struct MediaSource {
     pub container: String,
     #[serde(rename = "DirectStreamUrl")]
     pub direct_stream_url: Option<String>,
+    #[serde(rename = "SupportsDirectPlay")]
+    pub supports_direct_play: Option<bool>,
+    #[serde(rename = "SupportsDirectStream")]
+    pub supports_direct_stream: Option<bool>,
+    #[serde(rename = "TranscodingUrl")]
+    pub transcoding_url: Option<String>,
+    #[serde(rename = "Bitrate")]
+    pub bitrate: Option<u64>,
     #[serde(rename = "MediaStreams")]
     pub media_streams: Vec<MediaStream>,
+    #[serde(rename = "MediaAttachments", default)]
+    pub media_attachments: Vec<MediaAttachment>,
+}
+
+impl MediaSource {
+    /// The URL to play this source from, falling back to the server's transcode when the
+    /// file can't be streamed as is.
+    pub fn stream_url(&self) -> Option<&str> {
+        if self.supports_direct_stream != Some(false) {
+            if let Some(url) = &self.direct_stream_url {
+                return Some(url);
+            }
+        }
+        self.transcoding_url.as_deref()
+    }
+
+    /// How the server delivers the stream at [`Self::stream_url`].
+    pub fn play_method(&self) -> PlayMethod {
+        if self.supports_direct_stream == Some(false) || self.direct_stream_url.is_none() {
+            PlayMethod::Transcode
+        } else if self.supports_direct_play == Some(true) {
+            PlayMethod::DirectPlay
+        } else {
+            PlayMethod::DirectStream
+        }
+    }
+}
+
+/// A file embedded in the container, such as a font used by its ASS subtitles.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MediaAttachment {
+    #[serde(rename = "Index")]
+    pub index: u64,
+    #[serde(rename = "Codec")]
+    pub codec: Option<String>,
+    #[serde(rename = "FileName")]
+    pub file_name: Option<String>,
+    #[serde(rename = "MimeType")]
+    pub mime_type: Option<String>,
+    #[serde(rename = "DeliveryUrl")]
+    pub delivery_url: Option<String>,
+}
+
+impl MediaAttachment {
+    pub fn is_font(&self) -> bool {
+        const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "woff", "woff2"];
+
+        let mime_type = self.mime_type.as_deref().unwrap_or_default().to_lowercase();
+        if mime_type.contains("font") {
+            return true;
+        }
+        let extension = self
+            .file_name
+            .as_deref()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_lowercase());
+        [self.codec.as_deref().map(str::to_lowercase), extension]
+            .iter()
+            .flatten()
+            .any(|format| FONT_EXTENSIONS.contains(&format.as_str()))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -173,6 +250,52 @@ pub struct Item {
     pub artists: Option<Vec<String>>,
     #[serde(rename = "LockData")]
     pub lock_data: Option<bool>,
+    #[serde(rename = "Chapters")]
+    pub chapters: Option<Vec<ChapterInfo>>,
+    /// Tile sheet resolutions by media source id and then width
+    #[serde(rename = "Trickplay")]
+    pub trickplay: Option<HashMap<String, HashMap<String, TrickplayInfo>>>,
+}
+
+/// Thumbnails of a video laid out in grids on tile sheet images.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrickplayInfo {
+    #[serde(rename = "Width")]
+    pub width: u32,
+    #[serde(rename = "Height")]
+    pub height: u32,
+    #[serde(rename = "TileWidth")]
+    pub tile_width: u32,
+    #[serde(rename = "TileHeight")]
+    pub tile_height: u32,
+    #[serde(rename = "ThumbnailCount")]
+    pub thumbnail_count: u32,
+    /// Milliseconds between thumbnails
+    #[serde(rename = "Interval")]
+    pub interval: u32,
+}
+
+/// A chapter of a video. Emby also marks detected intros and credits as chapters, with
+/// a `MarkerType` of `IntroStart`, `IntroEnd` or `CreditsStart`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChapterInfo {
+    #[serde(rename = "StartPositionTicks")]
+    pub start_position_ticks: u64,
+    #[serde(rename = "Name")]
+    pub name: Option<String>,
+    #[serde(rename = "MarkerType")]
+    pub marker_type: Option<String>,
+    #[serde(rename = "ChapterIndex")]
+    pub chapter_index: Option<u32>,
+    #[serde(rename = "ImageTag")]
+    pub image_tag: Option<String>,
+}
+
+impl ChapterInfo {
+    /// Start of the chapter in seconds.
+    pub fn start(&self) -> f64 {
+        self.start_position_ticks as f64 / 10000000.0
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
@@ -437,6 +560,26 @@ pub struct RemoteSearchResult {
     pub image_url: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoteSubtitleInfo {
+    #[serde(rename = "Id")]
+    pub id: String,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "ProviderName")]
+    pub provider_name: Option<String>,
+    #[serde(rename = "Format")]
+    pub format: Option<String>,
+    #[serde(rename = "Author")]
+    pub author: Option<String>,
+    #[serde(rename = "CommunityRating")]
+    pub community_rating: Option<f32>,
+    #[serde(rename = "DownloadCount")]
+    pub download_count: Option<u64>,
+    #[serde(rename = "IsHashMatch")]
+    pub is_hash_match: Option<bool>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct ServerInfo {
     #[serde(rename = "ServerName")]
@@ -487,12 +630,39 @@ pub struct ActivityLogs {
     pub item: Vec<ActivityLog>,
 }
 
-#[derive(Deserialize, Debug, Clone, Builder)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Builder)]
 pub struct Back {
     pub id: String,
     pub playsessionid: Option<String>,
     pub mediasourceid: String,
     pub tick: u64,
+    #[builder(default)]
+    #[serde(default)]
+    pub state: PlaybackState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum PlayMethod {
+    DirectPlay,
+    #[default]
+    DirectStream,
+    Transcode,
+}
+
+/// Player state sent along with every playback report.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PlaybackState {
+    pub is_paused: bool,
+    pub volume: i64,
+    pub is_muted: bool,
+    pub speed: f64,
+    pub audio_stream_index: Option<i64>,
+    pub subtitle_stream_index: Option<i64>,
+    pub play_method: PlayMethod,
+    pub playlist_index: usize,
+    pub playlist_length: usize,
+    /// Buffered ranges as (start, end) ticks
+    pub buffered_ranges: Vec<(u64, u64)>,
 }
 
 #[derive(Deserialize)]
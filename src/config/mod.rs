@@ -25,6 +25,12 @@ pub struct Account {
     pub port: String,
     pub user_id: String,
     pub access_token: String,
+    /// Max streaming bitrate in bits per second on unmetered networks, 0 for no limit
+    #[serde(default)]
+    pub max_bitrate: u64,
+    /// Max streaming bitrate in bits per second on metered networks, 0 for no limit
+    #[serde(default)]
+    pub max_bitrate_metered: u64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -93,6 +99,35 @@ pub fn remove(account: &Account) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+pub fn update_account<F>(
+    servername: &str,
+    user_id: &str,
+    f: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnOnce(&mut Account),
+{
+    let mut path = get_config_dir()?;
+    path.push("tsukimi.toml");
+    let mut accounts: Accounts = load_cfgv2()?;
+    let Some(account) = accounts
+        .accounts
+        .iter_mut()
+        .find(|x| x.servername == servername && x.user_id == user_id)
+    else {
+        return Ok(());
+    };
+    f(account);
+    let toml = toml::to_string(&accounts)?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    writeln!(file, "{}", toml)?;
+    Ok(())
+}
+
 // Set %APPDATA%\tsukimi as config_dir on Windows
 pub fn get_config_dir() -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
     #[cfg(windows)]
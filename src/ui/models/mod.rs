@@ -1,5 +1,6 @@
 use once_cell::sync::Lazy;
 pub mod settings;
+pub mod texture_cache;
 use crate::client::client::EMBY_CLIENT;
 
 pub use self::settings::Settings;
@@ -20,3 +21,20 @@ pub fn emby_cache_path() -> std::path::PathBuf {
     }
     path
 }
+
+pub static DATA_PATH: Lazy<std::path::PathBuf> = Lazy::new(|| {
+    let path = gtk::glib::user_data_dir().join("tsukimi");
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Failed to create directory");
+    }
+    path
+});
+
+/// Per-server directory for state that must survive clearing the cache.
+pub fn emby_data_path() -> std::path::PathBuf {
+    let path = DATA_PATH.join(EMBY_CLIENT.server_name.lock().unwrap().as_str());
+    if !path.exists() {
+        std::fs::create_dir_all(&path).expect("Failed to create directory");
+    }
+    path
+}
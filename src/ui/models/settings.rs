@@ -46,6 +46,21 @@ impl Settings {
     const KEY_MPV_VIDEO_OUTPUT: &'static str = "mpv-video-output"; // i32
     const KEY_MPV_ACTION_AFTER_VIDEO_END: &'static str = "mpv-action-after-video-end"; // i32
     const KEY_MPV_HWDEC: &'static str = "mpv-hwdec"; // i32
+    const KEY_MPV_PLAYED_THRESHOLD: &'static str = "mpv-played-threshold"; // i32
+    const KEY_MPV_RESUME_MIN_POSITION: &'static str = "mpv-resume-min-position"; // i32
+    const KEY_MPV_RESUME_PROMPT: &'static str = "mpv-resume-prompt"; // bool
+    const KEY_MPV_UP_NEXT_SECONDS: &'static str = "mpv-up-next-seconds"; // i32
+    const KEY_MPV_REPEAT_MODE: &'static str = "mpv-repeat-mode"; // String
+    const KEY_MPV_SCREENSHOT_DIR: &'static str = "mpv-screenshot-dir"; // String
+    const KEY_MPV_SCREENSHOT_COPY: &'static str = "mpv-screenshot-copy"; // bool
+    const KEY_MPV_EXACT_SEEK: &'static str = "mpv-exact-seek"; // bool
+    const KEY_MPV_KEYBINDINGS: &'static str = "mpv-keybindings"; // Vec<String>
+    const KEY_MPV_AUDIO_LANG_PRIORITY: &'static str = "mpv-audio-lang-priority"; // Vec<String>
+    const KEY_MPV_SUBTITLE_LANG_PRIORITY: &'static str = "mpv-subtitle-lang-priority"; // Vec<String>
+    const KEY_MPV_SUBTITLE_FORCED_FOR_UI_LANG: &'static str = "mpv-subtitle-forced-for-ui-lang"; // bool
+    const KEY_MPV_SUBTITLE_ALLOW_SDH: &'static str = "mpv-subtitle-allow-sdh"; // bool
+    const KEY_MPV_SUBTITLE_SKIP_UNDERSTOOD_AUDIO: &'static str =
+        "mpv-subtitle-skip-understood-audio"; // bool
 
     pub fn set_mpv_hwdec(&self, mpv_hwdec: i32) -> Result<(), glib::BoolError> {
         self.set_int(Self::KEY_MPV_HWDEC, mpv_hwdec)
@@ -79,29 +94,71 @@ impl Settings {
         self.string(Self::KEY_MPV_SUBTITLE_FONT).to_string()
     }
 
-    pub fn set_mpv_audio_preferred_lang(
-        &self,
-        mpv_audio_preferred_lang: i32,
-    ) -> Result<(), glib::BoolError> {
-        self.set_int(Self::KEY_MPV_AUDIO_PREFERRED_LANG, mpv_audio_preferred_lang)
+    pub fn set_mpv_audio_lang_priority(&self, langs: &[&str]) -> Result<(), glib::BoolError> {
+        // The list replaces the single language picked in earlier versions
+        self.set_int(Self::KEY_MPV_AUDIO_PREFERRED_LANG, 0)?;
+        self.set_strv(Self::KEY_MPV_AUDIO_LANG_PRIORITY, langs)
     }
 
-    pub fn mpv_audio_preferred_lang(&self) -> i32 {
-        self.int(Self::KEY_MPV_AUDIO_PREFERRED_LANG)
+    /// Audio languages, most preferred first.
+    pub fn mpv_audio_lang_priority(&self) -> Vec<String> {
+        self.lang_priority(
+            Self::KEY_MPV_AUDIO_LANG_PRIORITY,
+            Self::KEY_MPV_AUDIO_PREFERRED_LANG,
+        )
     }
 
-    pub fn set_mpv_subtitle_preferred_lang(
-        &self,
-        mpv_subtitle_preferred_lang: i32,
-    ) -> Result<(), glib::BoolError> {
-        self.set_int(
+    pub fn set_mpv_subtitle_lang_priority(&self, langs: &[&str]) -> Result<(), glib::BoolError> {
+        self.set_int(Self::KEY_MPV_SUBTITLE_PREFERRED_LANG, 0)?;
+        self.set_strv(Self::KEY_MPV_SUBTITLE_LANG_PRIORITY, langs)
+    }
+
+    /// Subtitle languages, most preferred first.
+    pub fn mpv_subtitle_lang_priority(&self) -> Vec<String> {
+        self.lang_priority(
+            Self::KEY_MPV_SUBTITLE_LANG_PRIORITY,
             Self::KEY_MPV_SUBTITLE_PREFERRED_LANG,
-            mpv_subtitle_preferred_lang,
         )
     }
 
-    pub fn mpv_subtitle_preferred_lang(&self) -> i32 {
-        self.int(Self::KEY_MPV_SUBTITLE_PREFERRED_LANG)
+    fn lang_priority(&self, key: &str, legacy_key: &str) -> Vec<String> {
+        let langs = self.strv(key);
+        if !langs.is_empty() {
+            return langs.iter().map(|lang| lang.to_string()).collect();
+        }
+        match self.int(legacy_key) {
+            1 => vec!["eng".to_string()],
+            2 => vec!["chs".to_string()],
+            3 => vec!["jpn".to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn set_mpv_subtitle_forced_for_ui_lang(&self, forced: bool) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_SUBTITLE_FORCED_FOR_UI_LANG, forced)
+    }
+
+    pub fn mpv_subtitle_forced_for_ui_lang(&self) -> bool {
+        self.boolean(Self::KEY_MPV_SUBTITLE_FORCED_FOR_UI_LANG)
+    }
+
+    pub fn set_mpv_subtitle_allow_sdh(&self, allow_sdh: bool) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_SUBTITLE_ALLOW_SDH, allow_sdh)
+    }
+
+    pub fn mpv_subtitle_allow_sdh(&self) -> bool {
+        self.boolean(Self::KEY_MPV_SUBTITLE_ALLOW_SDH)
+    }
+
+    pub fn set_mpv_subtitle_skip_understood_audio(
+        &self,
+        skip: bool,
+    ) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_SUBTITLE_SKIP_UNDERSTOOD_AUDIO, skip)
+    }
+
+    pub fn mpv_subtitle_skip_understood_audio(&self) -> bool {
+        self.boolean(Self::KEY_MPV_SUBTITLE_SKIP_UNDERSTOOD_AUDIO)
     }
 
     pub fn set_mpv_default_volume(&self, mpv_default_volume: i32) -> Result<(), glib::BoolError> {
@@ -153,6 +210,91 @@ impl Settings {
         self.int(Self::KEY_MPV_ACTION_AFTER_VIDEO_END)
     }
 
+    pub fn set_mpv_played_threshold(
+        &self,
+        mpv_played_threshold: i32,
+    ) -> Result<(), glib::BoolError> {
+        self.set_int(Self::KEY_MPV_PLAYED_THRESHOLD, mpv_played_threshold)
+    }
+
+    pub fn mpv_played_threshold(&self) -> i32 {
+        self.int(Self::KEY_MPV_PLAYED_THRESHOLD)
+    }
+
+    pub fn set_mpv_resume_min_position(
+        &self,
+        mpv_resume_min_position: i32,
+    ) -> Result<(), glib::BoolError> {
+        self.set_int(Self::KEY_MPV_RESUME_MIN_POSITION, mpv_resume_min_position)
+    }
+
+    pub fn mpv_resume_min_position(&self) -> i32 {
+        self.int(Self::KEY_MPV_RESUME_MIN_POSITION)
+    }
+
+    pub fn set_mpv_resume_prompt(&self, mpv_resume_prompt: bool) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_RESUME_PROMPT, mpv_resume_prompt)
+    }
+
+    pub fn mpv_resume_prompt(&self) -> bool {
+        self.boolean(Self::KEY_MPV_RESUME_PROMPT)
+    }
+
+    pub fn set_mpv_up_next_seconds(&self, mpv_up_next_seconds: i32) -> Result<(), glib::BoolError> {
+        self.set_int(Self::KEY_MPV_UP_NEXT_SECONDS, mpv_up_next_seconds)
+    }
+
+    pub fn mpv_up_next_seconds(&self) -> i32 {
+        self.int(Self::KEY_MPV_UP_NEXT_SECONDS)
+    }
+
+    pub fn set_mpv_repeat_mode(&self, mpv_repeat_mode: &str) -> Result<(), glib::BoolError> {
+        self.set_string(Self::KEY_MPV_REPEAT_MODE, mpv_repeat_mode)
+    }
+
+    pub fn mpv_repeat_mode(&self) -> String {
+        self.string(Self::KEY_MPV_REPEAT_MODE).to_string()
+    }
+
+    pub fn set_mpv_screenshot_dir(&self, mpv_screenshot_dir: &str) -> Result<(), glib::BoolError> {
+        self.set_string(Self::KEY_MPV_SCREENSHOT_DIR, mpv_screenshot_dir)
+    }
+
+    pub fn mpv_screenshot_dir(&self) -> String {
+        self.string(Self::KEY_MPV_SCREENSHOT_DIR).to_string()
+    }
+
+    pub fn set_mpv_screenshot_copy(
+        &self,
+        mpv_screenshot_copy: bool,
+    ) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_SCREENSHOT_COPY, mpv_screenshot_copy)
+    }
+
+    pub fn mpv_screenshot_copy(&self) -> bool {
+        self.boolean(Self::KEY_MPV_SCREENSHOT_COPY)
+    }
+
+    pub fn set_mpv_exact_seek(&self, mpv_exact_seek: bool) -> Result<(), glib::BoolError> {
+        self.set_boolean(Self::KEY_MPV_EXACT_SEEK, mpv_exact_seek)
+    }
+
+    pub fn mpv_exact_seek(&self) -> bool {
+        self.boolean(Self::KEY_MPV_EXACT_SEEK)
+    }
+
+    pub fn set_mpv_keybindings(&self, keybindings: &[&str]) -> Result<(), glib::BoolError> {
+        self.set_strv(Self::KEY_MPV_KEYBINDINGS, keybindings)
+    }
+
+    /// Player keys as `KEY COMMAND` lines, empty for the defaults.
+    pub fn mpv_keybindings(&self) -> Vec<String> {
+        self.strv(Self::KEY_MPV_KEYBINDINGS)
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
     pub fn set_mpv_cache_time(&self, mpv_cache_time: i32) -> Result<(), glib::BoolError> {
         self.set_int(Self::KEY_MPV_CACHE_TIME, mpv_cache_time)
     }
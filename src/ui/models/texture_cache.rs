@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use gtk::{gdk, gio, prelude::*};
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+// Decoded RGBA textures are large, so the cache is bounded by bytes rather than entries
+const TEXTURE_CACHE_BUDGET: usize = 256 * 1024 * 1024;
+
+pub static TEXTURE_CACHE: Lazy<TextureCache> =
+    Lazy::new(|| TextureCache::new(TEXTURE_CACHE_BUDGET));
+
+#[derive(Default)]
+struct TextureCacheInner {
+    /// Textures with the stamp of their last use
+    textures: HashMap<String, (gdk::Texture, u64)>,
+    /// Keys by the stamp of their last use, least recently used first
+    order: BTreeMap<u64, String>,
+    next_stamp: u64,
+    size: usize,
+}
+
+impl TextureCacheInner {
+    fn stamp(&mut self) -> u64 {
+        self.next_stamp += 1;
+        self.next_stamp
+    }
+}
+
+/// In-memory LRU of decoded images, keyed by the image cache key (`{id}-{type}-{tag}`).
+pub struct TextureCache {
+    inner: Mutex<TextureCacheInner>,
+    budget: usize,
+}
+
+impl TextureCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            inner: Mutex::new(TextureCacheInner::default()),
+            budget,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<gdk::Texture> {
+        let mut inner = self.inner.lock().unwrap();
+        let stamp = inner.stamp();
+        let (texture, last_used) = inner.textures.get_mut(key)?;
+        let texture = texture.clone();
+        let last_used = std::mem::replace(last_used, stamp);
+        if let Some(k) = inner.order.remove(&last_used) {
+            inner.order.insert(stamp, k);
+        }
+        Some(texture)
+    }
+
+    pub fn insert(&self, key: &str, texture: gdk::Texture) {
+        let mut inner = self.inner.lock().unwrap();
+        let new_size = texture_size(&texture);
+        let stamp = inner.stamp();
+
+        if let Some((old, last_used)) = inner.textures.insert(key.to_string(), (texture, stamp)) {
+            inner.size -= texture_size(&old);
+            inner.order.remove(&last_used);
+        }
+        inner.order.insert(stamp, key.to_string());
+        inner.size += new_size;
+
+        while inner.size > self.budget && inner.order.len() > 1 {
+            let Some((_, oldest)) = inner.order.pop_first() else {
+                break;
+            };
+            if let Some((old, _)) = inner.textures.remove(&oldest) {
+                inner.size -= texture_size(&old);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.textures.clear();
+        inner.order.clear();
+        inner.size = 0;
+    }
+
+    /// Returns the cached texture for `key`, decoding `path` on a worker thread on a miss.
+    pub async fn load(&self, key: &str, path: PathBuf) -> Option<gdk::Texture> {
+        if let Some(texture) = self.get(key) {
+            return Some(texture);
+        }
+
+        let texture = match gio::spawn_blocking(move || gdk::Texture::from_filename(path)).await {
+            Ok(Ok(texture)) => texture,
+            Ok(Err(e)) => {
+                warn!("Failed to decode image {}: {}", key, e);
+                return None;
+            }
+            Err(_) => {
+                warn!("Image decoding thread panicked: {}", key);
+                return None;
+            }
+        };
+
+        self.insert(key, texture.clone());
+        Some(texture)
+    }
+}
+
+fn texture_size(texture: &gdk::Texture) -> usize {
+    texture.width() as usize * texture.height() as usize * 4
+}
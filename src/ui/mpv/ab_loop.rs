@@ -0,0 +1,83 @@
+/// Loop points of an A-B loop, in seconds.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct AbLoop {
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+}
+
+impl AbLoop {
+    /// The loop after setting a point at `position`: the start first, then the end, then
+    /// no loop again. An end before the start swaps the two.
+    pub fn next(self, position: f64) -> AbLoop {
+        match (self.a, self.b) {
+            (None, _) => AbLoop {
+                a: Some(position),
+                b: None,
+            },
+            (Some(a), None) if position < a => AbLoop {
+                a: Some(position),
+                b: Some(a),
+            },
+            // mpv doesn't loop a range with no length
+            (Some(a), None) if position == a => self,
+            (Some(a), None) => AbLoop {
+                a: Some(a),
+                b: Some(position),
+            },
+            (Some(_), Some(_)) => AbLoop::default(),
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.a.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AbLoop;
+
+    #[test]
+    fn next_sets_start_then_end_then_clears() {
+        let ab_loop = AbLoop::default().next(10.0);
+        assert_eq!(
+            ab_loop,
+            AbLoop {
+                a: Some(10.0),
+                b: None
+            }
+        );
+        assert!(ab_loop.is_set());
+
+        let ab_loop = ab_loop.next(20.0);
+        assert_eq!(
+            ab_loop,
+            AbLoop {
+                a: Some(10.0),
+                b: Some(20.0)
+            }
+        );
+
+        let ab_loop = ab_loop.next(30.0);
+        assert_eq!(ab_loop, AbLoop::default());
+        assert!(!ab_loop.is_set());
+    }
+
+    #[test]
+    fn next_swaps_end_before_start() {
+        let ab_loop = AbLoop::default().next(20.0).next(10.0);
+        assert_eq!(
+            ab_loop,
+            AbLoop {
+                a: Some(10.0),
+                b: Some(20.0)
+            }
+        );
+    }
+
+    #[test]
+    fn next_ignores_end_at_start() {
+        let ab_loop = AbLoop::default().next(10.0);
+        assert_eq!(ab_loop.next(10.0), ab_loop);
+    }
+}
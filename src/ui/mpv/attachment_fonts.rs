@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use crate::client::client::EMBY_CLIENT;
+use crate::client::structs::MediaSource;
+use crate::ui::models::emby_cache_path;
+use crate::utils::spawn_tokio;
+
+/// Downloads the fonts embedded in `media_source` of item `id`, which libass only sees when
+/// mpv demuxes the whole file. Returns the directory holding them, `None` if there are none.
+pub async fn fetch_fonts(id: &str, media_source: &MediaSource) -> Option<PathBuf> {
+    let fonts = media_source
+        .media_attachments
+        .iter()
+        .filter(|attachment| attachment.is_font())
+        .cloned()
+        .collect::<Vec<_>>();
+    if fonts.is_empty() {
+        return None;
+    }
+
+    let dir = emby_cache_path().join("fonts").join(id);
+    let id = id.to_string();
+    let media_source_id = media_source.id.clone();
+    let fonts_dir = dir.clone();
+    let fetched = spawn_tokio(async move {
+        std::fs::create_dir_all(&fonts_dir)?;
+        let mut fetched = 0;
+        for font in fonts {
+            // Only the bare name, the server doesn't get to pick the directory
+            let file_name = font
+                .file_name
+                .as_deref()
+                .and_then(|name| Path::new(name).file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("{}.ttf", font.index));
+            let path = fonts_dir.join(file_name);
+            if path.exists() {
+                fetched += 1;
+                continue;
+            }
+            match EMBY_CLIENT
+                .get_attachment(&id, &media_source_id, &font)
+                .await
+            {
+                Ok(bytes) => {
+                    std::fs::write(&path, bytes)?;
+                    fetched += 1;
+                }
+                Err(e) => warn!("Failed to download font attachment {}: {}", font.index, e),
+            }
+        }
+        Ok::<_, std::io::Error>(fetched)
+    })
+    .await;
+
+    match fetched {
+        Ok(0) => None,
+        Ok(_) => Some(dir),
+        Err(e) => {
+            warn!("Failed to cache font attachments: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::client::structs::ChapterInfo;
+
+/// The intro and credits Emby detected in a video, in seconds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChapterMarkers {
+    intro: Option<(f64, f64)>,
+    credits: Option<f64>,
+}
+
+impl ChapterMarkers {
+    pub fn from_chapters(chapters: &[ChapterInfo]) -> Self {
+        let marker = |marker_type: &str| {
+            chapters
+                .iter()
+                .find(|chapter| chapter.marker_type.as_deref() == Some(marker_type))
+                .map(ChapterInfo::start)
+        };
+        let intro = match (marker("IntroStart"), marker("IntroEnd")) {
+            (Some(start), Some(end)) if end > start => Some((start, end)),
+            _ => None,
+        };
+        Self {
+            intro,
+            credits: marker("CreditsStart").filter(|start| *start > 0.0),
+        }
+    }
+
+    /// Where the intro ends, while `position` is inside it.
+    pub fn intro_end(&self, position: f64) -> Option<f64> {
+        // Too close to the end to be worth skipping
+        self.intro
+            .filter(|(start, end)| position >= *start && position < end - 1.0)
+            .map(|(_, end)| end)
+    }
+
+    pub fn in_credits(&self, position: f64) -> bool {
+        self.credits.is_some_and(|start| position >= start)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::client::structs::ChapterInfo;
+
+use super::tsukimi_mpv::MpvChapter;
+
+// Seconds apart an mpv and an Emby chapter can start and still be the same chapter
+const SAME_CHAPTER_TOLERANCE: f64 = 1.0;
+
+/// A chapter as the player lists it.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: Option<String>,
+    /// Seconds
+    pub start: f64,
+    /// Index of the chapter image on the server, if it has one
+    pub image_index: Option<u32>,
+}
+
+/// Combines the chapters mpv found in the file with the ones Emby knows. mpv's are the
+/// ones chapter navigation moves between, so they win; Emby fills in titles and images.
+/// Emby's intro and credits markers are left out.
+pub fn merge_chapters(mpv_chapters: &[MpvChapter], emby_chapters: &[ChapterInfo]) -> Vec<Chapter> {
+    let emby_chapters = emby_chapters
+        .iter()
+        .enumerate()
+        .filter(|(_, chapter)| matches!(chapter.marker_type.as_deref(), None | Some("Chapter")))
+        .map(|(index, chapter)| Chapter {
+            title: chapter.name.clone().filter(|name| !name.is_empty()),
+            start: chapter.start(),
+            image_index: chapter
+                .image_tag
+                .as_ref()
+                .map(|_| chapter.chapter_index.unwrap_or(index as u32)),
+        })
+        .collect::<Vec<_>>();
+
+    if mpv_chapters.is_empty() {
+        return emby_chapters;
+    }
+
+    mpv_chapters
+        .iter()
+        .map(|mpv_chapter| {
+            let emby_chapter = emby_chapters
+                .iter()
+                .find(|chapter| (chapter.start - mpv_chapter.time).abs() < SAME_CHAPTER_TOLERANCE);
+            Chapter {
+                title: mpv_chapter
+                    .title
+                    .clone()
+                    .or_else(|| emby_chapter.and_then(|chapter| chapter.title.clone())),
+                start: mpv_chapter.time,
+                image_index: emby_chapter.and_then(|chapter| chapter.image_index),
+            }
+        })
+        .collect()
+}
+
+/// Index of the chapter playing at `position`.
+pub fn chapter_at(chapters: &[Chapter], position: f64) -> Option<usize> {
+    chapters
+        .iter()
+        .rposition(|chapter| chapter.start <= position)
+}
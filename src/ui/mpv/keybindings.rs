@@ -0,0 +1,468 @@
+use gettextrs::gettext;
+use gtk::glib;
+use tracing::warn;
+
+use crate::ui::models::SETTINGS;
+
+use super::tsukimi_mpv::normalize_keystr;
+
+// Written in place of the key of unbound commands, like in mpv's input.conf
+const UNBOUND_KEY: &str = "_";
+const ACTION_PREFIX: &str = "action ";
+
+/// Page actions that can be bound, with their default keys.
+const DEFAULT_ACTIONS: &[(&str, Option<&str>)] = &[
+    ("mpv.play-pause", None),
+    ("mpv.backward", None),
+    ("mpv.forward", None),
+    ("mpv.previous-video", Some("<")),
+    ("mpv.next-video", Some(">")),
+    ("mpv.chapter-prev", None),
+    ("mpv.chapter-next", None),
+    ("mpv.toggle-playlist", Some("F8")),
+    ("mpv.stats", Some("I")),
+    ("mpv.show-info", None),
+    ("mpv.go-to", Some("Ctrl+g")),
+    ("mpv.frame-back-step", Some(",")),
+    ("mpv.frame-step", Some(".")),
+    ("mpv.ab-loop", Some("l")),
+    ("mpv.ab-loop-clear", Some("L")),
+    ("mpv.exact-seek", Some("Ctrl+e")),
+    ("mpv.screenshot", Some("s")),
+    ("mpv.screenshot-video", Some("S")),
+];
+
+/// mpv commands bound out of the box, the same as mpv's own bindings for these keys.
+const DEFAULT_COMMANDS: &[(&str, &str)] = &[
+    ("[", "multiply speed 1/1.1"),
+    ("]", "multiply speed 1.1"),
+    ("{", "multiply speed 0.5"),
+    ("}", "multiply speed 2.0"),
+    ("BS", "set speed 1.0"),
+    ("z", "add sub-delay -0.1"),
+    ("x", "add sub-delay +0.1"),
+];
+
+/// What a key does in the player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingCommand {
+    /// An action of the player page, like `mpv.screenshot`
+    Action(String),
+    /// A command line for mpv, like `add sub-delay -0.1`
+    Mpv(String),
+}
+
+/// Sections of the shortcuts window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingGroup {
+    Playback,
+    Seeking,
+    Subtitles,
+    Screenshots,
+    Commands,
+}
+
+impl BindingGroup {
+    pub const ALL: [BindingGroup; 5] = [
+        BindingGroup::Playback,
+        BindingGroup::Seeking,
+        BindingGroup::Subtitles,
+        BindingGroup::Screenshots,
+        BindingGroup::Commands,
+    ];
+
+    pub fn title(&self) -> String {
+        match self {
+            BindingGroup::Playback => gettext("Playback"),
+            BindingGroup::Seeking => gettext("Seeking"),
+            BindingGroup::Subtitles => gettext("Subtitles"),
+            BindingGroup::Screenshots => gettext("Screenshots"),
+            BindingGroup::Commands => gettext("mpv Commands"),
+        }
+    }
+}
+
+impl BindingCommand {
+    /// What the command does, mpv commands without a known meaning show as themselves.
+    pub fn title(&self) -> String {
+        let name = match self {
+            BindingCommand::Action(name) => name.as_str(),
+            BindingCommand::Mpv(command) => command.as_str(),
+        };
+        match name {
+            "mpv.play-pause" => gettext("Play or pause"),
+            "mpv.backward" => gettext("Seek backward"),
+            "mpv.forward" => gettext("Seek forward"),
+            "mpv.previous-video" => gettext("Previous video"),
+            "mpv.next-video" => gettext("Next video"),
+            "mpv.chapter-prev" => gettext("Previous chapter"),
+            "mpv.chapter-next" => gettext("Next chapter"),
+            "mpv.toggle-playlist" => gettext("Show or hide the playlist"),
+            "mpv.stats" => gettext("Show or hide statistics"),
+            "mpv.show-info" => gettext("Media info"),
+            "mpv.go-to" => gettext("Go to time"),
+            "mpv.frame-back-step" => gettext("Step back one frame"),
+            "mpv.frame-step" => gettext("Step forward one frame"),
+            "mpv.ab-loop" => gettext("Set A-B loop start/end"),
+            "mpv.ab-loop-clear" => gettext("Clear A-B loop"),
+            "mpv.exact-seek" => gettext("Toggle exact seeking"),
+            "mpv.screenshot" => gettext("Take a screenshot"),
+            "mpv.screenshot-video" => gettext("Take a screenshot without subtitles"),
+            "multiply speed 1/1.1" => gettext("Decrease playback speed by 10%"),
+            "multiply speed 1.1" => gettext("Increase playback speed by 10%"),
+            "multiply speed 0.5" => gettext("Halve playback speed"),
+            "multiply speed 2.0" => gettext("Double playback speed"),
+            "set speed 1.0" => gettext("Reset playback speed to normal"),
+            "add sub-delay -0.1" => gettext("Decrease subtitle delay by 100 ms"),
+            "add sub-delay +0.1" => gettext("Increase subtitle delay by 100 ms"),
+            name => name.to_string(),
+        }
+    }
+
+    pub fn group(&self) -> BindingGroup {
+        match self {
+            BindingCommand::Action(name) => match name.as_str() {
+                "mpv.backward"
+                | "mpv.forward"
+                | "mpv.chapter-prev"
+                | "mpv.chapter-next"
+                | "mpv.go-to"
+                | "mpv.frame-back-step"
+                | "mpv.frame-step"
+                | "mpv.ab-loop"
+                | "mpv.ab-loop-clear"
+                | "mpv.exact-seek" => BindingGroup::Seeking,
+                "mpv.screenshot" | "mpv.screenshot-video" => BindingGroup::Screenshots,
+                _ => BindingGroup::Playback,
+            },
+            BindingCommand::Mpv(command) if command.contains("speed") => BindingGroup::Playback,
+            BindingCommand::Mpv(command) if command.contains("sub-") => BindingGroup::Subtitles,
+            BindingCommand::Mpv(_) => BindingGroup::Commands,
+        }
+    }
+
+    fn parse(command: &str) -> Option<Self> {
+        let command = command.trim();
+        if command.is_empty() {
+            return None;
+        }
+        Some(match command.strip_prefix(ACTION_PREFIX) {
+            Some(name) => BindingCommand::Action(name.trim().to_string()),
+            None => BindingCommand::Mpv(command.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for BindingCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingCommand::Action(name) => write!(f, "{}{}", ACTION_PREFIX, name),
+            BindingCommand::Mpv(command) => write!(f, "{}", command),
+        }
+    }
+}
+
+/// A key, written like mpv does, and what it does. Commands without a key stay listed so
+/// they can be bound again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keybinding {
+    pub key: Option<String>,
+    pub command: BindingCommand,
+}
+
+impl Keybinding {
+    // A line like `Ctrl+g action mpv.go-to` or `z add sub-delay -0.1`
+    fn parse(line: &str) -> Option<Self> {
+        let (key, command) = line.trim().split_once(char::is_whitespace)?;
+        let key = (key != UNBOUND_KEY).then(|| normalize_keystr(key));
+        Some(Self {
+            key,
+            command: BindingCommand::parse(command)?,
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{} {}",
+            self.key.as_deref().unwrap_or(UNBOUND_KEY),
+            self.command
+        )
+    }
+}
+
+/// The keys of the player, page actions and mpv commands alike. Keys that aren't bound
+/// go to mpv and its default bindings.
+#[derive(Debug, Clone, Default)]
+pub struct Keybindings {
+    bindings: Vec<Keybinding>,
+}
+
+impl Keybindings {
+    pub fn defaults() -> Self {
+        let actions = DEFAULT_ACTIONS.iter().map(|(name, key)| Keybinding {
+            key: key.map(normalize_keystr),
+            command: BindingCommand::Action(name.to_string()),
+        });
+        let commands = DEFAULT_COMMANDS.iter().map(|(key, command)| Keybinding {
+            key: Some(normalize_keystr(key)),
+            command: BindingCommand::Mpv(command.to_string()),
+        });
+        Self {
+            bindings: actions.chain(commands).collect(),
+        }
+    }
+
+    /// The map saved in the settings, or the default one. Actions added since the map was
+    /// saved get their default key when it's still free.
+    pub fn load() -> Self {
+        Self::from_lines(&SETTINGS.mpv_keybindings())
+    }
+
+    fn from_lines<S: AsRef<str>>(lines: &[S]) -> Self {
+        if lines.is_empty() {
+            return Self::defaults();
+        }
+
+        let mut keybindings = Self::default();
+        for line in lines.iter().map(AsRef::as_ref) {
+            match Keybinding::parse(line) {
+                Some(binding) => {
+                    if let Some(key) = &binding.key {
+                        if let Some(other) = keybindings.binding_for(key) {
+                            warn!(
+                                "{} is bound to both {} and {}, keeping the first",
+                                key, other.command, binding.command
+                            );
+                            continue;
+                        }
+                    }
+                    keybindings.bindings.push(binding);
+                }
+                None => warn!("Ignoring keybinding {:?}", line),
+            }
+        }
+
+        for (name, key) in DEFAULT_ACTIONS {
+            let command = BindingCommand::Action(name.to_string());
+            if keybindings.position(&command).is_some() {
+                continue;
+            }
+            let key = key
+                .map(normalize_keystr)
+                .filter(|key| keybindings.binding_for(key).is_none());
+            keybindings.bindings.push(Keybinding { key, command });
+        }
+        keybindings
+    }
+
+    pub fn save(&self) -> Result<(), glib::BoolError> {
+        let lines = self
+            .bindings
+            .iter()
+            .map(Keybinding::to_line)
+            .collect::<Vec<_>>();
+        SETTINGS.set_mpv_keybindings(&lines.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    pub fn bindings(&self) -> &[Keybinding] {
+        &self.bindings
+    }
+
+    /// What `key` does, `key` as `get_full_keystr` writes it.
+    pub fn lookup(&self, key: &str) -> Option<&BindingCommand> {
+        self.binding_for(&normalize_keystr(key))
+            .map(|binding| &binding.command)
+    }
+
+    /// The binding `key` would be taken from if it was given to the one at `index`.
+    pub fn conflict(&self, index: usize, key: &str) -> Option<&Keybinding> {
+        let key = normalize_keystr(key);
+        self.bindings
+            .iter()
+            .enumerate()
+            .find(|(i, binding)| *i != index && binding.key.as_deref() == Some(key.as_str()))
+            .map(|(_, binding)| binding)
+    }
+
+    /// Binds `key` to the command at `index`, taking it from any other command.
+    pub fn set_key(&mut self, index: usize, key: Option<&str>) {
+        let key = key.map(normalize_keystr);
+        if let Some(key) = &key {
+            for binding in &mut self.bindings {
+                if binding.key.as_ref() == Some(key) {
+                    binding.key = None;
+                }
+            }
+        }
+        if let Some(binding) = self.bindings.get_mut(index) {
+            binding.key = key;
+        }
+    }
+
+    /// Adds an unbound mpv command, returns where it is or already was.
+    pub fn add_command(&mut self, command: &str) -> Option<usize> {
+        let command = command.trim();
+        if command.is_empty() {
+            return None;
+        }
+        let command = BindingCommand::Mpv(command.to_string());
+        if let Some(index) = self.position(&command) {
+            return Some(index);
+        }
+        self.bindings.push(Keybinding { key: None, command });
+        Some(self.bindings.len() - 1)
+    }
+
+    /// Removes the binding at `index`, page actions stay and only lose their key.
+    pub fn remove(&mut self, index: usize) {
+        match self.bindings.get(index).map(|binding| &binding.command) {
+            Some(BindingCommand::Action(_)) => self.set_key(index, None),
+            Some(BindingCommand::Mpv(_)) => {
+                self.bindings.remove(index);
+            }
+            None => {}
+        }
+    }
+
+    fn binding_for(&self, key: &str) -> Option<&Keybinding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.key.as_deref() == Some(key))
+    }
+
+    fn position(&self, command: &BindingCommand) -> Option<usize> {
+        self.bindings
+            .iter()
+            .position(|binding| &binding.command == command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_of(keybindings: &Keybindings, command: &BindingCommand) -> Option<String> {
+        let index = keybindings.position(command)?;
+        keybindings.bindings()[index].key.clone()
+    }
+
+    fn action(name: &str) -> BindingCommand {
+        BindingCommand::Action(name.to_string())
+    }
+
+    #[test]
+    fn load_nothing_saved() {
+        let keybindings = Keybindings::from_lines::<&str>(&[]);
+        assert_eq!(keybindings.bindings(), Keybindings::defaults().bindings());
+    }
+
+    #[test]
+    fn load_lines() {
+        let keybindings = Keybindings::from_lines(&[
+            "Ctrl+p action mpv.play-pause",
+            "_ action mpv.screenshot",
+            "Shift+Z add sub-delay -1",
+        ]);
+        assert_eq!(
+            keybindings.lookup("Ctrl+p"),
+            Some(&action("mpv.play-pause"))
+        );
+        assert_eq!(key_of(&keybindings, &action("mpv.screenshot")), None);
+        assert_eq!(
+            keybindings.lookup("Z"),
+            Some(&BindingCommand::Mpv("add sub-delay -1".to_string()))
+        );
+    }
+
+    #[test]
+    fn load_keeps_first_of_a_key() {
+        let keybindings =
+            Keybindings::from_lines(&["x action mpv.play-pause", "x add sub-delay +0.1"]);
+        assert_eq!(keybindings.lookup("x"), Some(&action("mpv.play-pause")));
+        assert!(keybindings
+            .position(&BindingCommand::Mpv("add sub-delay +0.1".to_string()))
+            .is_none());
+    }
+
+    #[test]
+    fn load_adds_new_actions() {
+        // `s` is the default key of `mpv.screenshot`, `S` the one of `mpv.screenshot-video`
+        let keybindings = Keybindings::from_lines(&["s action mpv.play-pause"]);
+        assert_eq!(keybindings.lookup("s"), Some(&action("mpv.play-pause")));
+        assert_eq!(key_of(&keybindings, &action("mpv.screenshot")), None);
+        assert_eq!(
+            key_of(&keybindings, &action("mpv.screenshot-video")),
+            Some("S".to_string())
+        );
+        assert_eq!(
+            keybindings.bindings().len(),
+            DEFAULT_ACTIONS.len(),
+            "every action is listed once"
+        );
+    }
+
+    #[test]
+    fn load_skips_broken_lines() {
+        let keybindings = Keybindings::from_lines(&["x", "y   ", "z action mpv.stats"]);
+        assert_eq!(keybindings.lookup("z"), Some(&action("mpv.stats")));
+        assert_eq!(keybindings.lookup("x"), None);
+        assert_eq!(keybindings.lookup("y"), None);
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let keybindings = Keybindings::defaults();
+        let lines = keybindings
+            .bindings()
+            .iter()
+            .map(Keybinding::to_line)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            Keybindings::from_lines(&lines).bindings(),
+            keybindings.bindings()
+        );
+    }
+
+    #[test]
+    fn set_key_takes_key_from_other_command() {
+        let mut keybindings = Keybindings::defaults();
+        let stats = keybindings.position(&action("mpv.stats")).unwrap();
+        let go_to = keybindings.position(&action("mpv.go-to")).unwrap();
+
+        assert_eq!(
+            keybindings.conflict(stats, "Ctrl+g").map(|b| &b.command),
+            Some(&action("mpv.go-to"))
+        );
+        assert!(keybindings.conflict(go_to, "Ctrl+g").is_none());
+
+        keybindings.set_key(stats, Some("Ctrl+g"));
+        assert_eq!(keybindings.lookup("Ctrl+g"), Some(&action("mpv.stats")));
+        assert_eq!(keybindings.bindings()[go_to].key, None);
+    }
+
+    #[test]
+    fn set_key_folds_shift() {
+        let mut keybindings = Keybindings::defaults();
+        let stats = keybindings.position(&action("mpv.stats")).unwrap();
+        // `mpv.screenshot-video` has `S`, the same key as `Shift+S`
+        keybindings.set_key(stats, Some("Shift+S"));
+        assert_eq!(keybindings.bindings()[stats].key.as_deref(), Some("S"));
+        assert_eq!(key_of(&keybindings, &action("mpv.screenshot-video")), None);
+    }
+
+    #[test]
+    fn remove_unbinds_actions_and_drops_commands() {
+        let mut keybindings = Keybindings::defaults();
+        let stats = keybindings.position(&action("mpv.stats")).unwrap();
+        keybindings.remove(stats);
+        assert_eq!(keybindings.bindings()[stats].command, action("mpv.stats"));
+        assert_eq!(keybindings.bindings()[stats].key, None);
+
+        let index = keybindings.add_command("show-text hello").unwrap();
+        assert_eq!(keybindings.add_command(" show-text hello "), Some(index));
+        keybindings.remove(index);
+        assert!(keybindings
+            .position(&BindingCommand::Mpv("show-text hello".to_string()))
+            .is_none());
+    }
+}
@@ -0,0 +1,193 @@
+use gettextrs::gettext;
+use gtk::glib;
+
+use crate::client::structs::MediaStream;
+use crate::ui::models::SETTINGS;
+
+use super::tsukimi_mpv::{MpvTrack, TrackSelection};
+
+// Codes the same language goes by in containers, Emby and settings
+const LANGUAGES: &[&[&str]] = &[
+    &["eng", "en", "english"],
+    &["jpn", "ja", "jp", "japanese"],
+    &[
+        "chi", "zho", "zh", "chs", "cht", "cmn", "yue", "chinese", "zh-cn", "zh-tw", "zh-hans",
+        "zh-hant",
+    ],
+    &["kor", "ko", "korean"],
+    &["fre", "fra", "fr", "french"],
+    &["ger", "deu", "de", "german"],
+    &["spa", "es", "spanish"],
+    &["ita", "it", "italian"],
+    &["por", "pt", "portuguese"],
+    &["rus", "ru", "russian"],
+];
+
+pub fn canonical_language(lang: &str) -> String {
+    let lang = lang.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|codes| codes.contains(&lang.as_str()))
+        .map(|codes| codes[0].to_string())
+        .unwrap_or(lang)
+}
+
+pub fn same_language(a: &str, b: &str) -> bool {
+    !a.is_empty() && canonical_language(a) == canonical_language(b)
+}
+
+/// Language the interface is shown in, such as "zh" for "zh_CN.UTF-8".
+fn ui_language() -> Option<String> {
+    glib::language_names()
+        .first()
+        .and_then(|name| name.split(['_', '.', '@']).next())
+        .filter(|lang| !lang.is_empty() && *lang != "C")
+        .map(str::to_string)
+}
+
+/// A track as the rules see it, combining what mpv and Emby know about it.
+#[derive(Debug, Clone)]
+pub struct TrackInfo {
+    pub id: i64,
+    pub lang: String,
+    pub title: String,
+    pub forced: bool,
+    pub hearing_impaired: bool,
+}
+
+impl TrackInfo {
+    /// `stream` is the Emby media stream the track was loaded from, if known.
+    pub fn new(track: &MpvTrack, stream: Option<&MediaStream>) -> Self {
+        let lang = match stream.and_then(|stream| stream.language.clone()) {
+            Some(lang) if track.lang == "unknown" => lang,
+            _ => track.lang.clone(),
+        };
+        let title = stream
+            .and_then(|stream| stream.display_title.clone())
+            .unwrap_or_else(|| track.title.clone());
+        Self {
+            id: track.id,
+            hearing_impaired: track.hearing_impaired
+                || stream.and_then(|stream| stream.is_hearing_impaired) == Some(true)
+                || title.contains("SDH"),
+            forced: track.forced || stream.and_then(|stream| stream.is_forced) == Some(true),
+            lang,
+            title,
+        }
+    }
+}
+
+/// Ordered language preferences and the subtitle rules from the settings.
+pub struct LanguageRules {
+    audio: Vec<String>,
+    subtitle: Vec<String>,
+    ui_language: Option<String>,
+    forced_for_ui_language: bool,
+    allow_sdh: bool,
+    skip_understood_audio: bool,
+}
+
+impl LanguageRules {
+    pub fn from_settings() -> Self {
+        Self {
+            audio: SETTINGS.mpv_audio_lang_priority(),
+            subtitle: SETTINGS.mpv_subtitle_lang_priority(),
+            ui_language: ui_language(),
+            forced_for_ui_language: SETTINGS.mpv_subtitle_forced_for_ui_lang(),
+            allow_sdh: SETTINGS.mpv_subtitle_allow_sdh(),
+            skip_understood_audio: SETTINGS.mpv_subtitle_skip_understood_audio(),
+        }
+    }
+
+    /// The audio track in the most preferred language, `None` to leave it to mpv.
+    pub fn pick_audio<'a>(&self, tracks: &'a [TrackInfo]) -> Option<&'a TrackInfo> {
+        self.audio
+            .iter()
+            .find_map(|lang| tracks.iter().find(|track| same_language(&track.lang, lang)))
+    }
+
+    /// What to show for audio in `audio_lang`, `None` to leave it to mpv.
+    pub fn pick_subtitle(
+        &self,
+        audio_lang: Option<&str>,
+        tracks: &[TrackInfo],
+    ) -> Option<TrackSelection> {
+        if let Some(audio_lang) = audio_lang {
+            let matches_ui = self
+                .ui_language
+                .as_deref()
+                .is_some_and(|ui_lang| same_language(audio_lang, ui_lang));
+            if matches_ui && self.forced_for_ui_language {
+                // Forced subtitles only cover what isn't spoken in the audio language
+                if let Some(track) = tracks
+                    .iter()
+                    .find(|track| track.forced && same_language(&track.lang, audio_lang))
+                {
+                    return Some(TrackSelection::Track(track.id));
+                }
+            }
+            let understood = matches_ui
+                || self
+                    .subtitle
+                    .iter()
+                    .any(|lang| same_language(audio_lang, lang));
+            if understood && self.skip_understood_audio {
+                return Some(TrackSelection::None);
+            }
+        }
+
+        self.subtitle
+            .iter()
+            .find_map(|lang| {
+                let mut candidates = tracks
+                    .iter()
+                    .filter(|track| !track.forced && same_language(&track.lang, lang));
+                if self.allow_sdh {
+                    return candidates.next();
+                }
+                // SDH is only used when it is the only subtitle in the language
+                let candidates = candidates.collect::<Vec<_>>();
+                candidates
+                    .iter()
+                    .find(|track| !track.hearing_impaired)
+                    .or_else(|| candidates.first())
+                    .copied()
+            })
+            .map(|track| TrackSelection::Track(track.id))
+    }
+
+    /// Tracks picked for a sample video with Japanese and English audio and English,
+    /// English forced, English SDH and Chinese subtitles, as `(audio, subtitle)` titles.
+    pub fn preview(&self) -> (String, String) {
+        let track = |id, lang: &str, title: String, forced, hearing_impaired| TrackInfo {
+            id,
+            lang: lang.to_string(),
+            title,
+            forced,
+            hearing_impaired,
+        };
+        let audio_tracks = [
+            track(1, "jpn", gettext("Japanese"), false, false),
+            track(2, "eng", gettext("English"), false, false),
+        ];
+        let sub_tracks = [
+            track(1, "eng", gettext("English"), false, false),
+            track(2, "eng", gettext("English (Forced)"), true, false),
+            track(3, "eng", gettext("English (SDH)"), false, true),
+            track(4, "chi", gettext("Chinese"), false, false),
+        ];
+
+        // Without a preference mpv keeps the first track
+        let audio = self.pick_audio(&audio_tracks).unwrap_or(&audio_tracks[0]);
+        let subtitle = match self.pick_subtitle(Some(audio.lang.as_str()), &sub_tracks) {
+            Some(TrackSelection::Track(id)) => sub_tracks
+                .iter()
+                .find(|track| track.id == id)
+                .map(|track| track.title.clone())
+                .unwrap_or_default(),
+            Some(TrackSelection::None) => gettext("None"),
+            None => gettext("Default"),
+        };
+        (audio.title.clone(), subtitle)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::client::structs::MediaSource;
+
+/// What a picked media version looked like, so the closest version of the following
+/// episodes can be played as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionPreference {
+    height: Option<u64>,
+    container: String,
+    name_tokens: Vec<String>,
+}
+
+impl VersionPreference {
+    pub fn from_source(source: &MediaSource) -> Self {
+        Self {
+            height: video_height(source),
+            container: source.container.to_lowercase(),
+            name_tokens: name_tokens(&source.name),
+        }
+    }
+
+    /// The source closest to the preference: same resolution first, then container,
+    /// then the most words shared in the version name. Ties go to the earlier source.
+    pub fn best_match<'a>(&self, sources: &'a [MediaSource]) -> Option<&'a MediaSource> {
+        sources.iter().rev().max_by_key(|source| self.score(source))
+    }
+
+    fn score(&self, source: &MediaSource) -> i64 {
+        let mut score = match (self.height, video_height(source)) {
+            (Some(preferred), Some(height)) => -(preferred as i64 - height as i64).abs(),
+            (None, None) => 0,
+            _ => -500,
+        };
+        if source.container.to_lowercase() == self.container {
+            score += 100;
+        }
+        let tokens = name_tokens(&source.name);
+        score += 50
+            * self
+                .name_tokens
+                .iter()
+                .filter(|token| tokens.contains(token))
+                .count() as i64;
+        score
+    }
+}
+
+fn video_height(source: &MediaSource) -> Option<u64> {
+    source
+        .media_streams
+        .iter()
+        .find(|stream| stream.stream_type == "Video")
+        .and_then(|stream| stream.height)
+}
+
+/// Words of a version name that describe the version rather than the episode.
+fn name_tokens(name: &str) -> Vec<String> {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !is_episode_token(token))
+        .map(str::to_string)
+        .collect()
+}
+
+// Plain numbers and markers like "s01e02" change from one episode to the next
+fn is_episode_token(token: &str) -> bool {
+    if token.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    let Some(rest) = token.strip_prefix('s') else {
+        return false;
+    };
+    let Some((season, episode)) = rest.split_once('e') else {
+        return false;
+    };
+    [season, episode]
+        .iter()
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
@@ -1,5 +1,19 @@
+pub mod ab_loop;
+pub mod attachment_fonts;
+pub mod chapter_markers;
+pub mod chapters;
+pub mod keybindings;
+pub mod language_rules;
+pub mod media_version;
 pub mod menu_actions;
 pub mod mpvglarea;
 pub mod page;
+pub mod resume;
+pub mod screenshot;
+pub mod subtitle_style;
+pub mod track_memory;
+pub mod trickplay;
 pub mod tsukimi_mpv;
+pub mod up_next;
+pub mod video_queue;
 pub mod video_scale;
@@ -1,11 +1,17 @@
-use crate::client::client::{BackType, EMBY_CLIENT};
+use crate::client::client::{BackType, StreamFallback, EMBY_CLIENT};
 use crate::client::error::UserFacingError;
-use crate::client::structs::Back;
+use crate::client::outbox::{OutboxAction, OUTBOX};
+use crate::client::structs::{
+    Back, ChapterInfo, MediaSource, MediaStream, PlayMethod, PlaybackState,
+};
+use crate::gstl::player::imp::ListRepeatMode;
 use crate::toast;
 use crate::ui::models::SETTINGS;
 use crate::ui::provider::tu_item::TuItem;
 use crate::ui::widgets::check_row::CheckRow;
+use crate::ui::widgets::picture_loader::PictureLoader;
 use crate::ui::widgets::song_widget::format_duration;
+use crate::ui::widgets::subtitle_search_dialog::SubtitleSearchDialog;
 use crate::utils::{spawn, spawn_g_timeout, spawn_tokio};
 use adw::prelude::*;
 use gettextrs::gettext;
@@ -13,16 +19,55 @@ use glib::Object;
 use gtk::gdk::Rectangle;
 use gtk::subclass::prelude::*;
 use gtk::{gio, glib, Builder, PopoverMenu};
-
+use tracing::warn;
+
+use super::ab_loop::AbLoop;
+use super::attachment_fonts::fetch_fonts;
+use super::chapter_markers::ChapterMarkers;
+use super::chapters::{chapter_at, merge_chapters, Chapter};
+use super::keybindings::{BindingCommand, Keybindings};
+use super::language_rules::{LanguageRules, TrackInfo};
+use super::media_version::VersionPreference;
 use super::mpvglarea::MPVGLArea;
+use super::resume::{is_resumable_position, reached_played_threshold, start_percentage};
+use super::screenshot::{screenshot_dir, screenshot_path};
+use super::subtitle_style::SubtitleStyle;
+use super::track_memory::{
+    remember_subtitle_style, remember_tracks, set_skips_intro, skips_intro, subtitle_style,
+    track_preference, SubtitleChoice, TrackChoice,
+};
+use super::trickplay;
 use super::tsukimi_mpv::{
-    ListenEvent, MpvTrack, MpvTracks, TrackSelection, MPV_EVENT_CHANNEL, PAUSED,
+    get_full_keystr, ListenEvent, MpvChapter, MpvTrack, MpvTracks, TrackSelection,
+    END_FILE_REASON_ERROR, MPV_EVENT_CHANNEL, PAUSED,
 };
+use super::up_next::resolve_up_next;
 use super::video_scale::VideoScale;
 
 const MIN_MOTION_TIME: i64 = 100000;
 const NEXT_CHAPTER_KEYVAL: u32 = 65366;
 const PREV_CHAPTER_KEYVAL: u32 = 65365;
+const SHUTDOWN_REPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+// Seconds to wait for the server to list a downloaded subtitle
+const SUBTITLE_REFRESH_ATTEMPTS: u32 = 5;
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub", "sup", "idx", "smi"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum TrackKind {
+    Audio,
+    Subtitle,
+    SecondarySubtitle,
+}
+
+impl TrackKind {
+    fn property(self) -> &'static str {
+        match self {
+            TrackKind::Audio => "aid",
+            TrackKind::Subtitle => "sid",
+            TrackKind::SecondarySubtitle => "secondary-sid",
+        }
+    }
+}
 
 mod imp {
 
@@ -33,9 +78,21 @@ mod imp {
     use gtk::subclass::prelude::*;
     use gtk::{glib, CompositeTemplate, PopoverMenu, ShortcutsWindow};
 
-    use crate::client::structs::Back;
+    use crate::client::client::StreamFallback;
+    use crate::client::structs::{Back, ChapterInfo, MediaSource, PlayMethod};
+    use crate::gstl::player::imp::ListRepeatMode;
+    use crate::ui::models::SETTINGS;
+    use crate::ui::mpv::ab_loop::AbLoop;
+    use crate::ui::mpv::chapter_markers::ChapterMarkers;
+    use crate::ui::mpv::chapters::Chapter;
+    use crate::ui::mpv::keybindings::Keybindings;
+    use crate::ui::mpv::media_version::VersionPreference;
     use crate::ui::mpv::menu_actions::MenuActions;
     use crate::ui::mpv::mpvglarea::MPVGLArea;
+    use crate::ui::mpv::subtitle_style::SubtitleStyle;
+    use crate::ui::mpv::track_memory::TrackPreference;
+    use crate::ui::mpv::tsukimi_mpv::MpvChapter;
+    use crate::ui::mpv::up_next::UpNext;
     use crate::ui::mpv::video_scale::VideoScale;
     use crate::ui::provider::tu_item::TuItem;
     use crate::ui::widgets::action_row::AActionRow;
@@ -49,6 +106,12 @@ mod imp {
         pub url: RefCell<Option<String>>,
         #[property(get, set = Self::set_fullscreened, explicit_notify)]
         pub fullscreened: Cell<bool>,
+        /// Streaming bitrate limit picked in the quality menu, 0 for no limit
+        #[property(get, set)]
+        pub max_bitrate: Cell<u64>,
+        /// Whether relative seeks land on the exact frame instead of a keyframe
+        #[property(get, set = Self::set_exact_seek, explicit_notify)]
+        pub exact_seek: Cell<bool>,
         #[template_child]
         pub video: TemplateChild<MPVGLArea>,
         #[template_child]
@@ -85,6 +148,42 @@ mod imp {
         pub sub_listbox: TemplateChild<gtk::ListBox>,
         #[template_child]
         pub audio_listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub secondary_sub_listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub sub_delay_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub sub_position_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub sub_scale_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub sub_color_button: TemplateChild<gtk::ColorDialogButton>,
+        #[template_child]
+        pub sub_outline_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub sub_background_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub sub_ass_override_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub skip_intro_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub skip_intro_switchrow: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub next_episode_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub next_episode_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub up_next_picture_bin: TemplateChild<adw::Bin>,
+        #[template_child]
+        pub up_next_title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub up_next_subtitle_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub repeat_image: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub chapters_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub chapter_listbox: TemplateChild<gtk::ListBox>,
         pub timeout: RefCell<Option<glib::source::SourceId>>,
         pub back_timeout: RefCell<Option<glib::source::SourceId>>,
         pub back: RefCell<Option<Back>>,
@@ -101,6 +200,40 @@ mod imp {
 
         pub current_video: RefCell<Option<TuItem>>,
         pub current_episode_list: RefCell<Vec<TuItem>>,
+        pub play_method: Cell<PlayMethod>,
+        pub fallback: Cell<Option<StreamFallback>>,
+        pub last_position: Cell<f64>,
+        pub media_sources: RefCell<Vec<MediaSource>>,
+        pub version_preference: RefCell<Option<VersionPreference>>,
+        pub track_preference: RefCell<Option<TrackPreference>>,
+        pub tracks_applied: Cell<bool>,
+        pub library_id: RefCell<Option<String>>,
+        /// Set while the subtitle panel is filled in from a style rather than by the user
+        pub syncing_subtitle_style: Cell<bool>,
+        /// The style of mpv.conf, read before any other is applied
+        pub config_subtitle_style: RefCell<Option<SubtitleStyle>>,
+        /// Set once a style other than mpv.conf's was applied
+        pub subtitle_style_applied: Cell<bool>,
+        /// Emby's chapters, including the intro and credits markers
+        pub chapters: RefCell<Vec<ChapterInfo>>,
+        pub mpv_chapters: RefCell<Vec<MpvChapter>>,
+        /// What the chapter panel lists
+        pub merged_chapters: RefCell<Vec<Chapter>>,
+        pub chapter_markers: Cell<ChapterMarkers>,
+        /// Set once the intro was skipped, so seeking back into it is left alone
+        pub intro_skipped: Cell<bool>,
+        pub next_episode_timeout: RefCell<Option<glib::source::SourceId>>,
+        pub next_episode_remaining: Cell<u32>,
+        /// Set when the countdown was dismissed, until the credits and the end are left
+        pub next_episode_dismissed: Cell<bool>,
+        /// What plays next, once it is known
+        pub up_next: RefCell<Option<UpNext>>,
+        /// Set while the next video loads, so the countdown and the end of the file don't
+        /// both switch to it
+        pub switching_video: Cell<bool>,
+        pub repeat_mode: Cell<ListRepeatMode>,
+        pub ab_loop: Cell<AbLoop>,
+        pub keybindings: RefCell<Keybindings>,
     }
 
     // The central trait for subclassing a GObject
@@ -135,6 +268,31 @@ mod imp {
             klass.install_action("mpv.chapter-next", None, move |mpv, _action, _parameter| {
                 mpv.chapter_next();
             });
+            klass.install_property_action("mpv.quality", "max-bitrate");
+            klass.install_action_async(
+                "mpv.load-subtitle",
+                None,
+                |mpv, _action, _parameter| async move {
+                    mpv.on_load_subtitle().await;
+                },
+            );
+            klass.install_action("mpv.search-subtitles", None, |mpv, _action, _parameter| {
+                mpv.on_search_subtitles();
+            });
+            klass.install_action(
+                "mpv.save-subtitle-style",
+                None,
+                |mpv, _action, _parameter| {
+                    mpv.save_subtitle_style();
+                },
+            );
+            klass.install_action(
+                "mpv.reset-subtitle-style",
+                None,
+                |mpv, _action, _parameter| {
+                    mpv.set_subtitle_style(&mpv.config_subtitle_style());
+                },
+            );
             klass.install_action_async(
                 "mpv.next-video",
                 None,
@@ -149,6 +307,44 @@ mod imp {
                     mpv.on_previous_video().await;
                 },
             );
+            klass.install_action("mpv.repeat-none", None, |mpv, _action, _parameter| {
+                mpv.set_repeat_mode(ListRepeatMode::None);
+            });
+            klass.install_action("mpv.repeat-one", None, |mpv, _action, _parameter| {
+                mpv.set_repeat_mode(ListRepeatMode::RepeatOne);
+            });
+            klass.install_action("mpv.repeat-all", None, |mpv, _action, _parameter| {
+                mpv.set_repeat_mode(ListRepeatMode::Repeat);
+            });
+            klass.install_action("mpv.screenshot", None, |mpv, _action, _parameter| {
+                mpv.screenshot(true);
+            });
+            klass.install_action("mpv.screenshot-video", None, |mpv, _action, _parameter| {
+                mpv.screenshot(false);
+            });
+            klass.install_action("mpv.ab-loop", None, |mpv, _action, _parameter| {
+                mpv.cycle_ab_loop();
+            });
+            klass.install_action("mpv.ab-loop-clear", None, |mpv, _action, _parameter| {
+                mpv.set_ab_loop(AbLoop::default());
+                toast!(mpv, gettext("Loop cleared"));
+            });
+            klass.install_action("mpv.frame-step", None, |mpv, _action, _parameter| {
+                mpv.imp().video.imp().mpv.frame_step();
+            });
+            klass.install_action("mpv.frame-back-step", None, |mpv, _action, _parameter| {
+                mpv.imp().video.imp().mpv.frame_back_step();
+            });
+            klass.install_action_async("mpv.go-to", None, |mpv, _action, _parameter| async move {
+                mpv.go_to_time().await;
+            });
+            klass.install_property_action("mpv.exact-seek", "exact-seek");
+            klass.install_action("mpv.toggle-playlist", None, |mpv, _action, _parameter| {
+                mpv.on_playlist_clicked();
+            });
+            klass.install_action("mpv.stats", None, |mpv, _action, _parameter| {
+                mpv.imp().video.imp().mpv.display_stats_toggle();
+            });
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -181,6 +377,11 @@ mod imp {
             });
 
             obj.listen_events();
+            obj.connect_quality();
+            obj.setup_subtitle_drop();
+            obj.set_repeat_mode(ListRepeatMode::from_string(&SETTINGS.mpv_repeat_mode()));
+            obj.action_set_enabled("mpv.ab-loop-clear", false);
+            obj.reload_keybindings();
         }
     }
 
@@ -205,6 +406,18 @@ mod imp {
 
             self.obj().notify_fullscreened();
         }
+
+        fn set_exact_seek(&self, exact_seek: bool) {
+            if exact_seek == self.exact_seek.get() {
+                return;
+            }
+
+            self.exact_seek.set(exact_seek);
+            SETTINGS.set_mpv_exact_seek(exact_seek).unwrap();
+            self.video.imp().mpv.set_exact_seek(exact_seek);
+
+            self.obj().notify_exact_seek();
+        }
     }
 }
 
@@ -230,12 +443,14 @@ impl MPVPage {
     pub fn play(
         &self,
         url: &str,
+        play_method: PlayMethod,
         suburi: Option<&str>,
         item: TuItem,
         episode_list: Vec<TuItem>,
         back: Option<Back>,
         percentage: f64,
     ) {
+        self.set_max_bitrate(EMBY_CLIENT.streaming_bitrate_limit());
         let url = url.to_owned();
         let suburi = suburi.map(|s| s.to_owned());
         let name = if let Some(series_name) = item.series_name() {
@@ -250,20 +465,43 @@ impl MPVPage {
             item.name()
         };
 
-        self.imp().current_video.replace(Some(item));
+        // The previous video of a playlist still has an open session on the server
+        submit_reports(self.end_session());
+        // Until mpv reports a position the session is still where playback starts
+        self.imp()
+            .last_position
+            .set(item.run_time_ticks() as f64 / 10000000.0 * percentage / 100.0);
+        self.imp().fallback.set(None);
+        self.load_track_preference(&item);
+        self.imp().current_video.replace(Some(item.clone()));
         self.imp().current_episode_list.replace(episode_list);
+        self.load_chapters(&item);
+        self.load_up_next(&item);
+        self.set_ab_loop(AbLoop::default());
+        self.load_trickplay(&item, back.as_ref().map(|back| back.mediasourceid.clone()));
+        self.imp().play_method.set(play_method);
         spawn_g_timeout(glib::clone!(
             #[weak(rename_to = obj)]
             self,
             async move {
                 obj.load_config();
+                obj.load_subtitle_style();
                 let imp = obj.imp();
                 imp.spinner.set_visible(true);
                 imp.loading_box.set_visible(true);
                 imp.network_speed_label.set_text("Initializing...");
                 imp.title.set_text(&name);
+                // Shown by the stats overlay
+                imp.video.imp().mpv.set_media_title(&format!(
+                    "{} ({})",
+                    name,
+                    obj.playback_method_label()
+                ));
                 imp.suburl
                     .replace(suburi.map(|suburi| EMBY_CLIENT.get_streaming_url(&suburi)));
+                if !obj.load_attachment_fonts(back.as_ref()).await {
+                    return;
+                }
                 imp.video.play(&url, percentage);
                 imp.back.replace(back);
                 obj.handle_callback(BackType::Start);
@@ -271,19 +509,65 @@ impl MPVPage {
         ));
     }
 
+    /// Points mpv at the fonts embedded in the version about to play. Returns `false` if
+    /// another video was started while they were downloaded.
+    async fn load_attachment_fonts(&self, back: Option<&Back>) -> bool {
+        let imp = self.imp();
+        let media_source = back.and_then(|back| {
+            imp.media_sources
+                .borrow()
+                .iter()
+                .find(|media_source| media_source.id == back.mediasourceid)
+                .cloned()
+        });
+        let fonts_dir = match (back, media_source) {
+            (Some(back), Some(media_source)) => fetch_fonts(&back.id, &media_source).await,
+            _ => None,
+        };
+
+        let current = imp.current_video.borrow().as_ref().map(|item| item.id());
+        if back.is_some_and(|back| current.as_deref() != Some(back.id.as_str())) {
+            return false;
+        }
+        // mpv's own font directory
+        let fonts_dir = fonts_dir
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_else(|| "~~/fonts".to_string());
+        imp.video.imp().mpv.set_property("sub-fonts-dir", fonts_dir);
+        true
+    }
+
     fn set_audio_and_video_tracks_dropdown(&self, value: MpvTracks) {
         let imp = self.imp();
-        self.bind_tracks::<true>(value.audio_tracks, &imp.audio_listbox.get());
-        self.bind_tracks::<false>(value.sub_tracks, &imp.sub_listbox.get());
+        self.bind_tracks(
+            TrackKind::SecondarySubtitle,
+            value.sub_tracks.clone(),
+            &imp.secondary_sub_listbox.get(),
+        );
+        self.bind_tracks(
+            TrackKind::Audio,
+            value.audio_tracks,
+            &imp.audio_listbox.get(),
+        );
+        self.bind_tracks(
+            TrackKind::Subtitle,
+            value.sub_tracks,
+            &imp.sub_listbox.get(),
+        );
     }
 
     // TODO: Use GAction instead of listening to each button
-    fn bind_tracks<const A: bool>(&self, tracks: Vec<MpvTrack>, listbox: &gtk::ListBox) {
+    fn bind_tracks(&self, kind: TrackKind, tracks: Vec<MpvTrack>, listbox: &gtk::ListBox) {
         while let Some(row) = listbox.first_child() {
             listbox.remove(&row);
         }
 
-        let track_id = self.imp().video.get_track_id(if A { "aid" } else { "sid" });
+        let track_id = self.imp().video.get_track_id(kind.property());
+        let unloaded_subtitles = if kind == TrackKind::Audio {
+            Vec::new()
+        } else {
+            self.unloaded_external_subtitles(&tracks)
+        };
 
         let row = CheckRow::new();
         row.set_title("None");
@@ -295,7 +579,8 @@ impl MPVPage {
             #[weak(rename_to = obj)]
             self,
             move |_| {
-                obj.set_vsid::<A>(0);
+                obj.set_track(kind, 0);
+                obj.remember_track(kind, None);
             }
         ));
         listbox.append(&row);
@@ -310,114 +595,1241 @@ impl MPVPage {
             if track.id == track_id {
                 check.set_active(true);
             }
+            let choice = TrackChoice::from_track(&track);
+            row.connect_activated(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| {
+                    obj.set_track(kind, track.id);
+                    obj.remember_track(kind, Some(choice.clone()));
+                }
+            ));
+            listbox.append(&row);
+        }
+
+        // Loaded into mpv only once picked
+        for stream in unloaded_subtitles {
+            let Some(url) = stream.delivery_url.as_deref() else {
+                continue;
+            };
+            let url = EMBY_CLIENT.get_streaming_url(url);
+            let title = stream
+                .display_title
+                .clone()
+                .or_else(|| stream.title.clone())
+                .unwrap_or_else(|| gettext("External"));
+            let lang = stream.language.clone();
+
+            let row = CheckRow::new();
+            row.set_title(&title.replace('&', "&amp;"));
+            row.set_subtitle(&gettext("External"));
+            row.imp().check.get().set_group(Some(none_check));
             row.connect_activated(glib::clone!(
                 #[weak(rename_to = obj)]
                 self,
                 move |_| {
-                    obj.set_vsid::<A>(track.id);
+                    obj.add_subtitle(kind, &url, &title, lang.as_deref());
                 }
             ));
             listbox.append(&row);
         }
     }
 
-    fn set_vsid<const A: bool>(&self, track_id: i64) {
-        let track = if track_id == 0 {
-            TrackSelection::None
+    fn set_track(&self, kind: TrackKind, track_id: i64) {
+        let track = if track_id == 0 {
+            TrackSelection::None
+        } else {
+            TrackSelection::Track(track_id)
+        };
+
+        let video = &self.imp().video;
+        match kind {
+            TrackKind::Audio => video.set_aid(track),
+            TrackKind::Subtitle => video.set_sid(track),
+            TrackKind::SecondarySubtitle => video.imp().mpv.set_secondary_sid(track),
+        }
+    }
+
+    /// External subtitles of the playing version that mpv hasn't loaded yet.
+    fn unloaded_external_subtitles(&self, tracks: &[MpvTrack]) -> Vec<MediaStream> {
+        self.current_media_streams()
+            .into_iter()
+            .filter(|stream| {
+                stream.stream_type == "Subtitle"
+                    && stream.is_external
+                    && stream.delivery_url.is_some()
+            })
+            .filter(|stream| {
+                !tracks.iter().any(|track| {
+                    track.external_filename.is_some()
+                        && emby_stream_index(track, false) == Some(stream.index as i64)
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the subtitle at `url` into mpv and shows it as `kind`. Returns `false`, after
+    /// telling the user, if mpv couldn't load it.
+    fn add_subtitle(&self, kind: TrackKind, url: &str, title: &str, lang: Option<&str>) -> bool {
+        let mpv = &self.imp().video.imp().mpv;
+        let id = mpv
+            .add_sub_track(url, title, lang)
+            .then(|| mpv.track_list())
+            .flatten()
+            .and_then(|tracks| {
+                tracks
+                    .sub_tracks
+                    .iter()
+                    .rev()
+                    .find(|track| track.external_filename.as_deref() == Some(url))
+                    .map(|track| track.id)
+            });
+        let Some(id) = id else {
+            toast!(self, gettext("Failed to load the subtitle"));
+            return false;
+        };
+        self.set_track(kind, id);
+        true
+    }
+
+    /// Adds a subtitle file from disk, picked in a file dialog or dropped on the player.
+    fn load_subtitle_file(&self, file: &gio::File) -> bool {
+        if self.imp().current_video.borrow().is_none() {
+            return false;
+        }
+        let Some(path) = file.path() else {
+            return false;
+        };
+        let is_subtitle = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                SUBTITLE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            });
+        if !is_subtitle {
+            toast!(self, gettext("Not a subtitle file"));
+            return false;
+        }
+
+        let title = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.add_subtitle(TrackKind::Subtitle, &path.to_string_lossy(), &title, None)
+    }
+
+    async fn on_load_subtitle(&self) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Subtitles")));
+        for extension in SUBTITLE_EXTENSIONS {
+            filter.add_suffix(extension);
+        }
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+        let dialog = gtk::FileDialog::builder()
+            .modal(true)
+            .title(gettext("Load Subtitle File"))
+            .filters(&filters)
+            .build();
+
+        let window = self.root().and_downcast::<gtk::Window>();
+        if let Ok(file) = dialog.open_future(window.as_ref()).await {
+            self.load_subtitle_file(&file);
+        }
+    }
+
+    fn on_search_subtitles(&self) {
+        let imp = self.imp();
+        let Some(item) = imp.current_video.borrow().clone() else {
+            return;
+        };
+        let Some(media_source_id) = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone())
+        else {
+            return;
+        };
+
+        let dialog = SubtitleSearchDialog::new(&item.id(), &media_source_id);
+        dialog.connect_downloaded(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                spawn(glib::clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        obj.attach_downloaded_subtitle().await;
+                    }
+                ));
+            }
+        ));
+        dialog.present(Some(self));
+    }
+
+    /// Waits for the server to list a subtitle it just downloaded and adds it to mpv.
+    async fn attach_downloaded_subtitle(&self) {
+        let imp = self.imp();
+        let Some(item_id) = imp.current_video.borrow().as_ref().map(|item| item.id()) else {
+            return;
+        };
+        let Some(media_source_id) = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone())
+        else {
+            return;
+        };
+        let known = self
+            .current_media_streams()
+            .iter()
+            .map(|stream| stream.index)
+            .collect::<Vec<_>>();
+
+        for _ in 0..SUBTITLE_REFRESH_ATTEMPTS {
+            glib::timeout_future_seconds(1).await;
+
+            let id = item_id.clone();
+            let source_id = media_source_id.clone();
+            let media = match spawn_tokio(async move { EMBY_CLIENT.get_sub(&id, &source_id).await })
+                .await
+            {
+                Ok(media) => media,
+                Err(e) => {
+                    toast!(self, e.to_user_facing());
+                    return;
+                }
+            };
+
+            let Some(stream) = media
+                .media_sources
+                .iter()
+                .find(|media_source| media_source.id == media_source_id)
+                .and_then(|media_source| {
+                    media_source
+                        .media_streams
+                        .iter()
+                        .filter(|stream| {
+                            stream.stream_type == "Subtitle"
+                                && stream.is_external
+                                && !known.contains(&stream.index)
+                        })
+                        .last()
+                        .cloned()
+                })
+            else {
+                continue;
+            };
+
+            self.set_media_sources(media.media_sources, &media_source_id);
+            if let Some(url) = stream.delivery_url.as_deref() {
+                let title = stream
+                    .display_title
+                    .clone()
+                    .unwrap_or_else(|| gettext("External"));
+                let added = self.add_subtitle(
+                    TrackKind::Subtitle,
+                    &EMBY_CLIENT.get_streaming_url(url),
+                    &title,
+                    stream.language.as_deref(),
+                );
+                if !added {
+                    return;
+                }
+            }
+            toast!(self, gettext("Subtitle added"));
+            return;
+        }
+
+        toast!(
+            self,
+            gettext("Subtitle downloaded, but the server hasn't listed it yet")
+        );
+    }
+
+    fn setup_subtitle_drop(&self) {
+        let drop_target =
+            gtk::DropTarget::new(gio::File::static_type(), gtk::gdk::DragAction::COPY);
+        drop_target.connect_drop(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let Ok(file) = value.get::<gio::File>() else {
+                    return false;
+                };
+                obj.load_subtitle_file(&file)
+            }
+        ));
+        self.add_controller(drop_target);
+    }
+
+    /// Fetches the chapters of `item` along with the intro and credits markers among them.
+    fn load_chapters(&self, item: &TuItem) {
+        let imp = self.imp();
+        imp.chapters.replace(Vec::new());
+        imp.mpv_chapters.replace(Vec::new());
+        self.update_chapter_list();
+        imp.chapter_markers.set(ChapterMarkers::default());
+        imp.intro_skipped.set(false);
+        imp.next_episode_dismissed.set(false);
+        imp.skip_intro_button.set_visible(false);
+        self.stop_next_episode_countdown();
+
+        let series_id = item.series_id();
+        imp.skip_intro_switchrow.set_sensitive(series_id.is_some());
+        imp.skip_intro_switchrow
+            .set_active(series_id.as_deref().is_some_and(skips_intro));
+
+        let item_id = item.id();
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let id = item_id.clone();
+                let chapters =
+                    match spawn_tokio(async move { EMBY_CLIENT.get_chapters(&id).await }).await {
+                        Ok(chapters) => chapters,
+                        Err(e) => {
+                            warn!("Failed to load chapters of {}: {}", item_id, e);
+                            return;
+                        }
+                    };
+
+                let imp = obj.imp();
+                let current = imp.current_video.borrow().as_ref().map(|item| item.id());
+                if current.as_deref() != Some(item_id.as_str()) {
+                    return;
+                }
+                imp.chapter_markers
+                    .set(ChapterMarkers::from_chapters(&chapters));
+                imp.chapters.replace(chapters);
+                obj.update_chapter_list();
+            }
+        ));
+    }
+
+    /// Fetches the seek bar previews of `item` for the version about to play.
+    fn load_trickplay(&self, item: &TuItem, media_source_id: Option<String>) {
+        self.imp().video_scale.set_trickplay(None);
+        // Live streams have no previews
+        let Some(media_source_id) = media_source_id else {
+            return;
+        };
+
+        let item_id = item.id();
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let trickplay = trickplay::fetch(&item_id, &media_source_id).await;
+
+                let imp = obj.imp();
+                let current = imp.current_video.borrow().as_ref().map(|item| item.id());
+                if current.as_deref() != Some(item_id.as_str()) {
+                    return;
+                }
+                imp.video_scale.set_trickplay(trickplay);
+            }
+        ));
+    }
+
+    /// Lists the chapters of mpv and Emby in the chapter panel and as ticks on the
+    /// progress bar.
+    fn update_chapter_list(&self) {
+        let imp = self.imp();
+        let chapters = merge_chapters(&imp.mpv_chapters.borrow(), &imp.chapters.borrow());
+
+        let starts = chapters
+            .iter()
+            .map(|chapter| chapter.start)
+            .collect::<Vec<_>>();
+        imp.video_scale.set_chapter_marks(&starts);
+        imp.chapters_button.set_visible(!chapters.is_empty());
+        if chapters.is_empty() {
+            imp.chapters_button.set_active(false);
+        }
+
+        let item_id = imp.current_video.borrow().as_ref().map(|item| item.id());
+        imp.chapter_listbox.remove_all();
+        for (index, chapter) in chapters.iter().enumerate() {
+            imp.chapter_listbox
+                .append(&chapter_row(index, chapter, item_id.as_deref()));
+        }
+
+        let current = chapter_at(&chapters, imp.video.position());
+        imp.merged_chapters.replace(chapters);
+        self.select_chapter(current);
+    }
+
+    fn on_chapter_changed(&self, index: i64) {
+        // Without chapters in the file mpv has no current chapter to report
+        if self.imp().mpv_chapters.borrow().is_empty() {
+            return;
+        }
+        self.select_chapter(usize::try_from(index).ok());
+    }
+
+    fn select_chapter(&self, index: Option<usize>) {
+        let listbox = &self.imp().chapter_listbox;
+        let row = index.and_then(|index| listbox.row_at_index(index as i32));
+        if listbox.selected_row() != row {
+            listbox.select_row(row.as_ref());
+        }
+    }
+
+    #[template_callback]
+    fn on_chapter_activated(&self, row: &gtk::ListBoxRow, _listbox: &gtk::ListBox) {
+        let imp = self.imp();
+        let start = imp
+            .merged_chapters
+            .borrow()
+            .get(row.index() as usize)
+            .map(|chapter| chapter.start);
+        if let Some(start) = start {
+            imp.video.set_position(start);
+        }
+    }
+
+    /// Looks up the tracks remembered for the series and library of `item`. They are
+    /// picked once mpv knows the tracks of the file.
+    fn load_track_preference(&self, item: &TuItem) {
+        let imp = self.imp();
+        imp.track_preference.replace(None);
+        imp.library_id.replace(None);
+
+        let series_id = item.series_id();
+        let item_id = item.id();
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let id = item_id.clone();
+                let library_id =
+                    match spawn_tokio(async move { EMBY_CLIENT.get_library_id(&id).await }).await {
+                        Ok(library_id) => library_id,
+                        Err(e) => {
+                            warn!("Failed to look up library of {}: {}", item_id, e);
+                            None
+                        }
+                    };
+
+                let imp = obj.imp();
+                // Another video may have been started meanwhile
+                let current = imp.current_video.borrow().as_ref().map(|item| item.id());
+                if current.as_deref() != Some(item_id.as_str()) {
+                    return;
+                }
+                let preference = track_preference(series_id.as_deref(), library_id.as_deref());
+                imp.library_id.replace(library_id);
+                imp.track_preference.replace(Some(preference));
+                obj.apply_track_preference();
+            }
+        ));
+    }
+
+    /// Picks the remembered tracks, or else the ones the language rules prefer. Done once
+    /// per file and only until the user picks a track.
+    fn apply_track_preference(&self) {
+        let imp = self.imp();
+        if imp.tracks_applied.get() {
+            return;
+        }
+        let Some(preference) = imp.track_preference.borrow().clone() else {
+            return;
+        };
+        let Some(tracks) = imp.video.imp().mpv.track_list() else {
+            return;
+        };
+        if tracks.audio_tracks.is_empty() && tracks.sub_tracks.is_empty() {
+            return;
+        }
+        imp.tracks_applied.set(true);
+
+        let rules = LanguageRules::from_settings();
+        let streams = self.current_media_streams();
+        let direct_play = self.is_direct_play();
+        let track_info = |track: &MpvTrack| {
+            let index = emby_stream_index(track, direct_play);
+            let stream = streams
+                .iter()
+                .find(|stream| Some(stream.index as i64) == index);
+            TrackInfo::new(track, stream)
+        };
+        let audio_tracks = tracks
+            .audio_tracks
+            .iter()
+            .map(track_info)
+            .collect::<Vec<_>>();
+        let sub_tracks = tracks.sub_tracks.iter().map(track_info).collect::<Vec<_>>();
+
+        // What was picked for the series wins over the language rules
+        let audio_id = preference
+            .audio
+            .as_ref()
+            .and_then(|choice| choice.best_match(&tracks.audio_tracks))
+            .map(|track| track.id)
+            .or_else(|| rules.pick_audio(&audio_tracks).map(|track| track.id));
+        if let Some(id) = audio_id {
+            imp.video.set_aid(TrackSelection::Track(id));
+        }
+
+        let audio_lang = audio_id
+            .or_else(|| {
+                tracks
+                    .audio_tracks
+                    .iter()
+                    .find(|track| track.selected)
+                    .map(|track| track.id)
+            })
+            .and_then(|id| audio_tracks.iter().find(|track| track.id == id))
+            .map(|track| track.lang.as_str());
+        let subtitle = match preference.subtitle {
+            Some(SubtitleChoice::Off) => Some(TrackSelection::None),
+            Some(SubtitleChoice::Track(choice)) => choice
+                .best_match(&tracks.sub_tracks)
+                .map(|track| TrackSelection::Track(track.id)),
+            None => None,
+        }
+        .or_else(|| rules.pick_subtitle(audio_lang, &sub_tracks));
+        if let Some(subtitle) = subtitle {
+            imp.video.set_sid(subtitle);
+        }
+    }
+
+    /// Emby's description of the streams in the playing version.
+    fn current_media_streams(&self) -> Vec<MediaStream> {
+        let imp = self.imp();
+        let Some(media_source_id) = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone())
+        else {
+            return Vec::new();
+        };
+        imp.media_sources
+            .borrow()
+            .iter()
+            .find(|media_source| media_source.id == media_source_id)
+            .map(|media_source| media_source.media_streams.clone())
+            .unwrap_or_default()
+    }
+
+    fn remember_track(&self, kind: TrackKind, choice: Option<TrackChoice>) {
+        let imp = self.imp();
+        // Don't let a late lookup override what the user just picked
+        imp.tracks_applied.set(true);
+
+        let series_id = imp
+            .current_video
+            .borrow()
+            .as_ref()
+            .and_then(|item| item.series_id());
+        let library_id = imp.library_id.borrow().clone();
+        match kind {
+            TrackKind::Audio => {
+                // Muting isn't something to carry over
+                let Some(choice) = choice else {
+                    return;
+                };
+                remember_tracks(series_id.as_deref(), library_id.as_deref(), |preference| {
+                    preference.audio = Some(choice.clone())
+                });
+            }
+            TrackKind::Subtitle => {
+                let choice = match choice {
+                    Some(choice) => SubtitleChoice::Track(choice),
+                    None => SubtitleChoice::Off,
+                };
+                remember_tracks(series_id.as_deref(), library_id.as_deref(), |preference| {
+                    preference.subtitle = Some(choice.clone())
+                });
+            }
+            TrackKind::SecondarySubtitle => {}
+        }
+    }
+
+    async fn load_video(&self, offset: isize) {
+        toast!(self, gettext("Loading Video..."));
+
+        let Some(current_video) = self.imp().current_video.borrow().clone() else {
+            return;
+        };
+
+        let video_list = self.imp().current_episode_list.borrow().clone();
+
+        let next_item = if offset == 1 {
+            // Usually resolved while the video played
+            let up_next = match self.imp().up_next.take() {
+                Some(up_next) => Some(up_next),
+                None => {
+                    resolve_up_next(&current_video, &video_list, self.imp().repeat_mode.get()).await
+                }
+            };
+            up_next.map(|up_next| {
+                if let Some(playlist) = up_next.playlist {
+                    if let Some(window) = self
+                        .root()
+                        .and_downcast_ref::<crate::ui::widgets::window::Window>()
+                    {
+                        window.set_mpv_playlist(&playlist);
+                    }
+                    self.imp().current_episode_list.replace(playlist);
+                }
+                up_next.item
+            })
+        } else {
+            video_list.iter().enumerate().find_map(|(i, item)| {
+                if item.id() == current_video.id() {
+                    let new_index = (i as isize + offset) as usize;
+                    video_list.get(new_index).cloned()
+                } else {
+                    None
+                }
+            })
+        };
+
+        let Some(next_item) = next_item else {
+            toast!(self, gettext("No more videos found"));
+            self.on_stop_clicked();
+            return;
+        };
+
+        self.in_play_item(next_item).await;
+    }
+
+    pub async fn in_play_item(&self, item: TuItem) {
+        let Some(percentage) = start_percentage(self, &item).await else {
+            return;
+        };
+
+        self.play_item(item, None, None, percentage).await;
+    }
+
+    /// Requests a stream of `item` and plays it. `media_source_id` keeps the version that
+    /// was playing, otherwise the version closest to the one picked last is used.
+    async fn play_item(
+        &self,
+        item: TuItem,
+        media_source_id: Option<String>,
+        suburi: Option<String>,
+        percentage: f64,
+    ) {
+        toast!(self, gettext("Waiting for mediasource..."));
+
+        let item_id = item.id();
+
+        let video_list = self.imp().current_episode_list.borrow().clone();
+
+        let playback =
+            match spawn_tokio(async move { EMBY_CLIENT.get_playbackinfo(&item_id).await })
+                .await
+            {
+                Ok(playback) => playback,
+                Err(e) => {
+                    toast!(self, e.to_user_facing());
+                    return;
+                }
+            };
+
+        let media_source = media_source_id
+            .as_deref()
+            .and_then(|id| {
+                playback
+                    .media_sources
+                    .iter()
+                    .find(|media_source| media_source.id == id)
+            })
+            .or_else(|| {
+                self.imp()
+                    .version_preference
+                    .borrow()
+                    .as_ref()
+                    .and_then(|preference| preference.best_match(&playback.media_sources))
+            })
+            .or_else(|| playback.media_sources.first());
+
+        let Some((media_source_id, url, play_method)) = media_source.and_then(|media_source| {
+            Some((
+                media_source.id.clone(),
+                media_source.stream_url()?.to_owned(),
+                media_source.play_method(),
+            ))
+        }) else {
+            toast!(self, gettext("No media sources found"));
+            return;
+        };
+
+        let back = Back {
+            id: item.id(),
+            playsessionid: playback.play_session_id,
+            mediasourceid: media_source_id.clone(),
+            tick: 0,
+            state: PlaybackState::default(),
+        };
+
+        self.play(
+            &url,
+            play_method,
+            suburi.as_deref(),
+            item.clone(),
+            video_list,
+            Some(back),
+            percentage,
+        );
+        self.set_media_sources(playback.media_sources, &media_source_id);
+    }
+
+    /// Remembers `preference` for choosing the version of the following videos.
+    pub fn set_version_preference(&self, preference: Option<VersionPreference>) {
+        self.imp().version_preference.replace(preference);
+    }
+
+    /// Sets the versions offered for the playing video and shows them in the playlist
+    /// sidebar.
+    pub fn set_media_sources(&self, media_sources: Vec<MediaSource>, current_id: &str) {
+        let names = media_sources
+            .iter()
+            .map(|media_source| media_source.name.clone())
+            .collect::<Vec<_>>();
+        let selected = media_sources
+            .iter()
+            .position(|media_source| media_source.id == current_id)
+            .unwrap_or(0) as u32;
+        self.imp().media_sources.replace(media_sources);
+
+        if let Some(window) = self
+            .root()
+            .and_downcast_ref::<crate::ui::widgets::window::Window>()
+        {
+            window.set_mpv_versions(&names, selected);
+        }
+    }
+
+    /// Switches the playing video to the version at `index` and prefers it from now on.
+    pub async fn select_version(&self, index: u32) {
+        let imp = self.imp();
+        let Some(media_source) = imp.media_sources.borrow().get(index as usize).cloned() else {
+            return;
+        };
+        let playing = imp
+            .back
+            .borrow()
+            .as_ref()
+            .is_some_and(|back| back.mediasourceid == media_source.id);
+        if playing {
+            return;
+        }
+
+        self.set_version_preference(Some(VersionPreference::from_source(&media_source)));
+        // Subtitles of the old version don't belong to the new one
+        imp.suburl.replace(None);
+        self.reload_video(Some(media_source.id)).await;
+    }
+
+    /// Retries the current video with the next server-side fallback after mpv failed to
+    /// open it. Returns `false` once there is nothing left to fall back to.
+    async fn try_fallback(&self) -> bool {
+        let imp = self.imp();
+        let Some(fallback) = StreamFallback::after(imp.fallback.get()) else {
+            return false;
+        };
+        let Some(item) = imp.current_video.borrow().clone() else {
+            return false;
+        };
+        let Some(media_source_id) = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone())
+        else {
+            return false;
+        };
+
+        let state = self.playback_state();
+        let duration = match imp.video_scale.adjustment().upper() {
+            duration if duration > 0.0 => duration,
+            _ => item.run_time_ticks() as f64 / 10000000.0,
+        };
+        let percentage = if duration > 0.0 {
+            self.report_position() / duration * 100.0
+        } else {
+            0.0
+        };
+        let mut suburi = imp.suburl.borrow().clone();
+        let video_list = imp.current_episode_list.borrow().clone();
+
+        let item_id = item.id();
+        let source_id = media_source_id.clone();
+        let playback = match spawn_tokio(async move {
+            EMBY_CLIENT
+                .get_fallback_playbackinfo(
+                    &item_id,
+                    &source_id,
+                    fallback,
+                    state.audio_stream_index,
+                    state.subtitle_stream_index,
+                )
+                .await
+        })
+        .await
+        {
+            Ok(playback) => playback,
+            Err(e) => {
+                warn!("Failed to request {:?} fallback: {}", fallback, e);
+                return false;
+            }
+        };
+
+        let Some(media_source) = playback
+            .media_sources
+            .iter()
+            .find(|media_source| media_source.id == media_source_id)
+            .or_else(|| playback.media_sources.first())
+        else {
+            return false;
+        };
+        let Some(url) = media_source.stream_url() else {
+            return false;
+        };
+        // Subtitles the server no longer muxes into the stream are delivered separately
+        if let Some(delivery_url) = media_source
+            .media_streams
+            .iter()
+            .find(|stream| Some(stream.index as i64) == state.subtitle_stream_index)
+            .and_then(|stream| stream.delivery_url.clone())
+        {
+            suburi = Some(delivery_url);
+        }
+
+        let back = Back {
+            id: item.id(),
+            playsessionid: playback.play_session_id.clone(),
+            mediasourceid: media_source.id.clone(),
+            tick: 0,
+            state: PlaybackState::default(),
+        };
+
+        let method = match fallback {
+            StreamFallback::Remux => gettext("Remux"),
+            StreamFallback::Transcode => gettext("Transcode"),
+        };
+        toast!(
+            self,
+            format!(
+                "{}: {}",
+                gettext("Direct stream failed, retrying with"),
+                method
+            )
+        );
+
+        self.play(
+            url,
+            media_source.play_method(),
+            suburi.as_deref(),
+            item,
+            video_list,
+            Some(back),
+            percentage,
+        );
+        imp.fallback.set(Some(fallback));
+        true
+    }
+
+    /// Whether mpv plays the original file, so its tracks keep the container's stream
+    /// indices.
+    fn is_direct_play(&self) -> bool {
+        let imp = self.imp();
+        imp.fallback.get().is_none() && imp.play_method.get() == PlayMethod::DirectPlay
+    }
+
+    fn playback_method_label(&self) -> String {
+        let imp = self.imp();
+        match (imp.fallback.get(), imp.play_method.get()) {
+            (Some(StreamFallback::Remux), _) => gettext("Remux"),
+            (Some(StreamFallback::Transcode), _) | (None, PlayMethod::Transcode) => {
+                gettext("Transcode")
+            }
+            (None, PlayMethod::DirectPlay) => gettext("Direct Play"),
+            (None, PlayMethod::DirectStream) => gettext("Direct Stream"),
+        }
+    }
+
+    fn connect_quality(&self) {
+        self.connect_max_bitrate_notify(|obj| {
+            spawn(glib::clone!(
+                #[weak]
+                obj,
+                async move {
+                    obj.on_max_bitrate_changed().await;
+                }
+            ));
+        });
+    }
+
+    async fn on_max_bitrate_changed(&self) {
+        let max_bitrate = self.max_bitrate();
+        if max_bitrate == EMBY_CLIENT.streaming_bitrate_limit() {
+            return;
+        }
+        if let Err(e) = EMBY_CLIENT.set_streaming_bitrate_limit(max_bitrate) {
+            toast!(self, e.to_user_facing());
+        }
+        self.reload_video(None).await;
+    }
+
+    /// Restarts the current video from the same position with a freshly negotiated stream,
+    /// switching to `media_source_id` if given.
+    async fn reload_video(&self, media_source_id: Option<String>) {
+        let imp = self.imp();
+        let Some(item) = imp.current_video.borrow().clone() else {
+            return;
+        };
+        // Live streams have no session to renegotiate
+        let Some(current_id) = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone())
+        else {
+            return;
+        };
+        let media_source_id = media_source_id.unwrap_or(current_id);
+        let suburi = imp.suburl.borrow().clone();
+        let duration = imp.video_scale.adjustment().upper();
+        let percentage = if duration > 0.0 {
+            self.report_position() / duration * 100.0
+        } else {
+            0.0
+        };
+        self.play_item(item, Some(media_source_id), suburi, percentage)
+            .await;
+    }
+
+    pub async fn on_next_video(&self) {
+        let imp = self.imp();
+        if imp.switching_video.replace(true) {
+            return;
+        }
+        self.stop_next_episode_countdown();
+        self.load_video(1).await;
+        imp.switching_video.set(false);
+    }
+
+    pub async fn on_previous_video(&self) {
+        self.load_video(-1).await;
+    }
+
+    /// Plays the current video again from the start, in the same version.
+    async fn replay(&self) {
+        let imp = self.imp();
+        let Some(item) = imp.current_video.borrow().clone() else {
+            return;
+        };
+        let media_source_id = imp
+            .back
+            .borrow()
+            .as_ref()
+            .map(|back| back.mediasourceid.clone());
+        self.play_item(item, media_source_id, None, 0.0).await;
+    }
+
+    /// Plays `queue` from `item`, its first video, as the playlist.
+    pub async fn play_queue(&self, item: TuItem, queue: Vec<TuItem>, percentage: f64) {
+        self.imp().current_episode_list.replace(queue);
+        self.play_item(item, None, None, percentage).await;
+    }
+
+    pub fn set_repeat_mode(&self, mode: ListRepeatMode) {
+        let imp = self.imp();
+        imp.repeat_mode.set(mode);
+        SETTINGS.set_mpv_repeat_mode(mode.to_string()).unwrap();
+        imp.repeat_image.set_icon_name(Some(match mode {
+            ListRepeatMode::None => "media-playlist-consecutive-symbolic",
+            ListRepeatMode::RepeatOne => "media-playlist-repeat-song-symbolic",
+            ListRepeatMode::Repeat => "media-playlist-repeat-symbolic",
+        }));
+
+        // What plays next depends on the mode
+        let current_video = imp.current_video.borrow().clone();
+        if let Some(item) = current_video {
+            self.load_up_next(&item);
+        }
+    }
+
+    /// Sets the next loop point at the current position, or clears the loop once both
+    /// are set.
+    pub fn cycle_ab_loop(&self) {
+        let position = self.imp().video.position();
+        let ab_loop = self.imp().ab_loop.get().next(position);
+        self.set_ab_loop(ab_loop);
+        match (ab_loop.a, ab_loop.b) {
+            (Some(a), None) => toast!(
+                self,
+                format!(
+                    "{} {}",
+                    gettext("Loop start set at"),
+                    format_duration(a as i64)
+                )
+            ),
+            (Some(a), Some(b)) => toast!(
+                self,
+                format!(
+                    "{} {} - {}",
+                    gettext("Looping"),
+                    format_duration(a as i64),
+                    format_duration(b as i64)
+                )
+            ),
+            _ => toast!(self, gettext("Loop cleared")),
+        }
+    }
+
+    pub fn set_ab_loop(&self, ab_loop: AbLoop) {
+        let imp = self.imp();
+        imp.ab_loop.set(ab_loop);
+        imp.video.imp().mpv.set_ab_loop(ab_loop.a, ab_loop.b);
+        imp.video_scale.set_ab_loop(ab_loop);
+        self.action_set_enabled("mpv.ab-loop-clear", ab_loop.is_set());
+    }
+
+    /// Asks for a time like `1:02:03`, `12:34` or `90` and seeks exactly to it.
+    pub async fn go_to_time(&self) {
+        let imp = self.imp();
+        if imp.current_video.borrow().is_none() {
+            return;
+        }
+
+        let entry = gtk::Entry::builder()
+            .placeholder_text(format_duration(imp.video.position() as i64))
+            .activates_default(true)
+            .input_purpose(gtk::InputPurpose::Number)
+            .build();
+        let dialog = adw::AlertDialog::new(Some(&gettext("Go to Time")), None);
+        dialog.set_extra_child(Some(&entry));
+        dialog.add_responses(&[
+            ("cancel", gettext("Cancel").as_str()),
+            ("go", gettext("Go").as_str()),
+        ]);
+        dialog.set_response_appearance("go", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("go"));
+        dialog.set_close_response("cancel");
+
+        if dialog.choose_future(Some(self)).await.as_str() != "go" {
+            return;
+        }
+        let Some(position) = parse_time(&entry.text()) else {
+            toast!(self, gettext("Invalid time"));
+            return;
+        };
+        let duration = imp.video_scale.adjustment().upper();
+        let position = if duration > 0.0 {
+            position.min(duration)
         } else {
-            TrackSelection::Track(track_id)
+            position
         };
+        imp.video.imp().mpv.seek_exact(position);
+    }
 
-        if A {
-            self.imp().video.set_aid(track);
-        } else {
-            self.imp().video.set_sid(track);
+    #[template_callback]
+    fn on_progress_value_changed(&self, progress_scale: &VideoScale) {
+        let label = &self.imp().progress_time_label.get();
+        let position = progress_scale.value();
+        if position > 0.0 {
+            self.imp().last_position.set(position);
         }
+        label.set_text(&format_duration(position as i64));
+        self.update_chapter_markers(position);
     }
 
-    async fn load_video(&self, offset: isize) {
-        toast!(self, gettext("Loading Video..."));
-
-        let Some(current_video) = self.imp().current_video.borrow().clone() else {
-            return;
-        };
+    /// Offers to skip the intro, or skips it right away if asked to for the series, and
+    /// counts down to the next episode once the credits start or the end is near.
+    fn update_chapter_markers(&self, position: f64) {
+        let imp = self.imp();
+        if imp.mpv_chapters.borrow().is_empty() {
+            self.select_chapter(chapter_at(&imp.merged_chapters.borrow(), position));
+        }
 
-        let video_list = self.imp().current_episode_list.borrow().clone();
+        let markers = imp.chapter_markers.get();
 
-        let next_item = video_list.iter().enumerate().find_map(|(i, item)| {
-            if item.id() == current_video.id() {
-                let new_index = (i as isize + offset) as usize;
-                video_list.get(new_index).cloned()
-            } else {
-                None
+        let intro_end = markers.intro_end(position);
+        if let Some(intro_end) = intro_end {
+            if imp.skip_intro_switchrow.is_active() && !imp.intro_skipped.get() {
+                imp.intro_skipped.set(true);
+                imp.video.set_position(intro_end);
+                toast!(self, gettext("Intro skipped"));
+                return;
             }
-        });
+        }
+        imp.skip_intro_button.set_visible(intro_end.is_some());
 
-        let Some(next_item) = next_item else {
-            toast!(self, gettext("No more videos found"));
-            self.on_stop_clicked();
-            return;
-        };
+        if markers.in_credits(position) || self.near_end(position) {
+            self.start_next_episode_countdown();
+        } else {
+            imp.next_episode_dismissed.set(false);
+            self.stop_next_episode_countdown();
+        }
+    }
 
-        self.in_play_item(next_item).await;
+    #[template_callback]
+    fn on_skip_intro_clicked(&self) {
+        let imp = self.imp();
+        let position = imp.video.position();
+        if let Some(intro_end) = imp.chapter_markers.get().intro_end(position) {
+            imp.intro_skipped.set(true);
+            imp.video.set_position(intro_end);
+        }
+        imp.skip_intro_button.set_visible(false);
     }
 
-    pub async fn in_play_item(&self, item: TuItem) {
-        toast!(self, gettext("Waiting for mediasource..."));
+    #[template_callback]
+    fn on_skip_intro_toggled(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
+        let series_id = self
+            .imp()
+            .current_video
+            .borrow()
+            .as_ref()
+            .and_then(|item| item.series_id());
+        if let Some(series_id) = series_id {
+            set_skips_intro(&series_id, control.is_active());
+        }
+    }
 
-        let item_id = item.id();
+    /// Looks up what plays after `item` and fills in the up next card with it.
+    fn load_up_next(&self, item: &TuItem) {
+        let imp = self.imp();
+        imp.up_next.replace(None);
+        imp.up_next_picture_bin.set_child(None::<&gtk::Widget>);
 
-        let video_list = self.imp().current_episode_list.borrow().clone();
+        let item = item.clone();
+        let playlist = imp.current_episode_list.borrow().clone();
+        let repeat_mode = imp.repeat_mode.get();
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let up_next = resolve_up_next(&item, &playlist, repeat_mode).await;
 
-        let playback =
-            match spawn_tokio(async move { EMBY_CLIENT.get_playbackinfo(&item_id).await })
-                .await
-            {
-                Ok(playback) => playback,
-                Err(e) => {
-                    toast!(self, e.to_user_facing());
+                let imp = obj.imp();
+                let current = imp.current_video.borrow().as_ref().map(|item| item.id());
+                if current != Some(item.id()) {
                     return;
                 }
-            };
+                if let Some(up_next) = &up_next {
+                    obj.set_up_next_card(&up_next.item);
+                }
+                imp.up_next.replace(up_next);
+            }
+        ));
+    }
 
-        let (media_source_id, url) = if let Some(media_source) = playback.media_sources.first() {
-            (
-                media_source.id.clone(),
-                media_source.direct_stream_url.clone(),
+    fn set_up_next_card(&self, item: &TuItem) {
+        let imp = self.imp();
+        imp.up_next_title_label.set_text(&item.name());
+        let subtitle = item.series_name().map(|series_name| {
+            format!(
+                "{} - S{}E{}",
+                series_name,
+                item.parent_index_number(),
+                item.index_number()
             )
-        } else {
-            toast!(self, gettext("No media sources found"));
-            return;
-        };
+        });
+        imp.up_next_subtitle_label
+            .set_text(subtitle.as_deref().unwrap_or_default());
+        imp.up_next_subtitle_label.set_visible(subtitle.is_some());
+        let picture = PictureLoader::new(&item.id(), "Primary", None);
+        imp.up_next_picture_bin.set_child(Some(&picture));
+    }
 
-        let Some(url) = url else {
-            toast!(self, gettext("No media sources found"));
+    /// Whether `position` is within the last seconds the up next card is shown for.
+    fn near_end(&self, position: f64) -> bool {
+        let duration = self.imp().video_scale.adjustment().upper();
+        let seconds = SETTINGS.mpv_up_next_seconds();
+        seconds > 0 && duration > 0.0 && position >= duration - seconds as f64
+    }
+
+    fn start_next_episode_countdown(&self) {
+        let imp = self.imp();
+        let seconds = SETTINGS.mpv_up_next_seconds();
+        if imp.next_episode_timeout.borrow().is_some()
+            || imp.next_episode_dismissed.get()
+            || imp.up_next.borrow().is_none()
+            || imp.repeat_mode.get() == ListRepeatMode::RepeatOne
+            || seconds <= 0
+        {
             return;
-        };
+        }
 
-        let back = Back {
-            id: item.id(),
-            playsessionid: playback.play_session_id,
-            mediasourceid: media_source_id.to_string(),
-            tick: 0,
-        };
+        imp.next_episode_remaining.set(seconds as u32);
+        self.update_next_episode_label();
+        imp.next_episode_box.set_visible(true);
+
+        let source = glib::timeout_add_seconds_local(
+            1,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || obj.on_next_episode_tick()
+            ),
+        );
+        imp.next_episode_timeout.replace(Some(source));
+    }
+
+    fn on_next_episode_tick(&self) -> glib::ControlFlow {
+        let imp = self.imp();
+        // The countdown waits while the credits are paused
+        if imp.video.paused() {
+            return glib::ControlFlow::Continue;
+        }
+
+        let remaining = imp.next_episode_remaining.get().saturating_sub(1);
+        imp.next_episode_remaining.set(remaining);
+        if remaining > 0 {
+            self.update_next_episode_label();
+            return glib::ControlFlow::Continue;
+        }
 
-        self.play(&url, None, item.clone(), video_list, Some(back), 0.0);
+        // The source is removed by returning `Break`
+        imp.next_episode_timeout.take();
+        imp.next_episode_box.set_visible(false);
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                obj.on_next_video().await;
+            }
+        ));
+        glib::ControlFlow::Break
     }
 
-    pub async fn on_next_video(&self) {
-        self.load_video(1).await;
+    fn stop_next_episode_countdown(&self) {
+        let imp = self.imp();
+        if let Some(source) = imp.next_episode_timeout.take() {
+            source.remove();
+        }
+        imp.next_episode_box.set_visible(false);
     }
 
-    pub async fn on_previous_video(&self) {
-        self.load_video(-1).await;
+    fn update_next_episode_label(&self) {
+        let imp = self.imp();
+        imp.next_episode_button.set_label(&format!(
+            "{} ({})",
+            gettext("Play Now"),
+            imp.next_episode_remaining.get()
+        ));
     }
 
     #[template_callback]
-    fn on_progress_value_changed(&self, progress_scale: &VideoScale) {
-        let label = &self.imp().progress_time_label.get();
-        let position = progress_scale.value();
-        label.set_text(&format_duration(position as i64));
+    fn on_next_episode_dismissed(&self) {
+        self.imp().next_episode_dismissed.set(true);
+        self.stop_next_episode_countdown();
     }
 
     #[template_callback]
@@ -458,6 +1870,7 @@ impl MPVPage {
                             obj.on_start_file();
                         }
                         ListenEvent::TrackList(value) => {
+                            obj.apply_track_preference();
                             obj.set_audio_and_video_tracks_dropdown(value);
                         }
                         ListenEvent::Volume(value) => {
@@ -466,6 +1879,16 @@ impl MPVPage {
                         ListenEvent::Speed(value) => {
                             obj.speed_cb(value);
                         }
+                        ListenEvent::SubDelay(value) => {
+                            obj.sub_delay_cb(value);
+                        }
+                        ListenEvent::ChapterList(value) => {
+                            obj.imp().mpv_chapters.replace(value);
+                            obj.update_chapter_list();
+                        }
+                        ListenEvent::Chapter(value) => {
+                            obj.on_chapter_changed(value);
+                        }
                     }
                 }
             }
@@ -501,8 +1924,104 @@ impl MPVPage {
         imp.video.set_volume(btn.value() as i64);
     }
 
+    /// Applies the subtitle style saved for the series of the current video. Without one
+    /// mpv.conf's style is left alone, unless an earlier file changed it, since mpv keeps
+    /// the last style across files.
+    fn load_subtitle_style(&self) {
+        let imp = self.imp();
+        let series_id = imp
+            .current_video
+            .borrow()
+            .as_ref()
+            .and_then(|item| item.series_id());
+        self.action_set_enabled("mpv.save-subtitle-style", series_id.is_some());
+        let config_style = self.config_subtitle_style();
+        match series_id.and_then(|id| subtitle_style(&id)) {
+            Some(style) => self.set_subtitle_style(&style),
+            None if imp.subtitle_style_applied.get() => self.set_subtitle_style(&config_style),
+            None => self.show_subtitle_style(&config_style),
+        }
+    }
+
+    fn config_subtitle_style(&self) -> SubtitleStyle {
+        let imp = self.imp();
+        imp.config_subtitle_style
+            .borrow_mut()
+            .get_or_insert_with(|| SubtitleStyle::from_mpv(&imp.video.imp().mpv))
+            .clone()
+    }
+
+    fn set_subtitle_style(&self, style: &SubtitleStyle) {
+        let imp = self.imp();
+        style.apply(&imp.video.imp().mpv);
+        imp.subtitle_style_applied.set(true);
+        self.show_subtitle_style(style);
+    }
+
+    /// Fills in the subtitle panel without applying anything.
+    fn show_subtitle_style(&self, style: &SubtitleStyle) {
+        let imp = self.imp();
+        imp.syncing_subtitle_style.set(true);
+        imp.sub_delay_row.set_value(style.delay);
+        imp.sub_position_row.set_value(style.position);
+        imp.sub_scale_row.set_value(style.scale);
+        imp.sub_color_button.set_rgba(&style.rgba());
+        imp.sub_outline_row.set_value(style.outline_size);
+        imp.sub_background_row.set_active(style.background);
+        imp.sub_ass_override_row.set_selected(style.ass_override);
+        imp.syncing_subtitle_style.set(false);
+    }
+
+    fn panel_subtitle_style(&self) -> SubtitleStyle {
+        let imp = self.imp();
+        let mut style = SubtitleStyle {
+            delay: imp.sub_delay_row.value(),
+            position: imp.sub_position_row.value(),
+            scale: imp.sub_scale_row.value(),
+            outline_size: imp.sub_outline_row.value(),
+            background: imp.sub_background_row.is_active(),
+            ass_override: imp.sub_ass_override_row.selected(),
+            ..Default::default()
+        };
+        style.set_rgba(&imp.sub_color_button.rgba());
+        style
+    }
+
+    #[template_callback]
+    fn on_subtitle_style_changed(&self, _param: glib::ParamSpec, _control: glib::Object) {
+        let imp = self.imp();
+        if imp.syncing_subtitle_style.get() {
+            return;
+        }
+        self.panel_subtitle_style().apply(&imp.video.imp().mpv);
+        imp.subtitle_style_applied.set(true);
+    }
+
+    // Keeps the panel in step with the delay keys mpv handles itself
+    fn sub_delay_cb(&self, value: f64) {
+        let imp = self.imp();
+        imp.syncing_subtitle_style.set(true);
+        imp.sub_delay_row.set_value(value);
+        imp.syncing_subtitle_style.set(false);
+    }
+
+    fn save_subtitle_style(&self) {
+        let Some(series_id) = self
+            .imp()
+            .current_video
+            .borrow()
+            .as_ref()
+            .and_then(|item| item.series_id())
+        else {
+            return;
+        };
+        remember_subtitle_style(&series_id, &self.panel_subtitle_style());
+        toast!(self, gettext("Subtitle style saved for this series"));
+    }
+
     fn on_start_file(&self) {
         let imp = self.imp();
+        imp.tracks_applied.set(false);
         if let Some(suburl) = imp.suburl.borrow().as_ref() {
             imp.video.add_sub(suburl);
         }
@@ -526,8 +2045,18 @@ impl MPVPage {
             #[weak(rename_to = obj)]
             self,
             async move {
-                if value == 0 {
+                if value == END_FILE_REASON_ERROR {
+                    if !obj.try_fallback().await {
+                        toast!(obj, gettext("Failed to play the video"));
+                        submit_reports(obj.end_session());
+                    }
+                } else if value == 0 {
                     match SETTINGS.mpv_action_after_video_end() {
+                        // Cancelling up next keeps the video open
+                        0 if obj.imp().next_episode_dismissed.get() => {}
+                        0 if obj.imp().repeat_mode.get() == ListRepeatMode::RepeatOne => {
+                            obj.replay().await
+                        }
                         0 => obj.on_next_video().await,
                         2 => obj.on_stop_clicked(),
                         _ => {}
@@ -537,12 +2066,18 @@ impl MPVPage {
         ));
     }
 
+    /// Failed property and command replies, the video keeps playing. Files that can't be
+    /// played end in [`Self::on_end_file`].
     fn on_error(&self, value: &str) {
         toast!(self, value);
     }
 
     fn on_pause_update(&self, value: bool) {
         self.pause_icon_set(value);
+        // Let the server know right away instead of on the next progress tick
+        if self.imp().back.borrow().is_some() {
+            self.handle_callback(BackType::Back);
+        }
     }
 
     fn on_cache_speed_update(&self, value: i64) {
@@ -704,14 +2239,7 @@ impl MPVPage {
 
     #[template_callback]
     fn on_stop_clicked(&self) {
-        self.handle_callback(BackType::Stop);
-        self.remove_timeout();
-        self.imp().video_scale.remove_timeout();
-        let mpv = &self.imp().video.imp().mpv;
-        mpv.pause(true);
-        mpv.stop();
-        mpv.event_thread_alive
-            .store(PAUSED, std::sync::atomic::Ordering::SeqCst);
+        submit_reports(self.stop_playback());
         let root = self.root();
         let window = root
             .and_downcast_ref::<crate::ui::widgets::window::Window>()
@@ -725,21 +2253,126 @@ impl MPVPage {
     }
 
     fn handle_callback(&self, backtype: BackType) {
-        let position = &self.imp().video.position();
-        let back = self.imp().back.borrow();
+        if let Some(back) = self.current_back() {
+            submit_reports(vec![OutboxAction::Playback(back, backtype)]);
+        }
+    }
+
+    /// Captures the current position and player state for a report to the server.
+    fn current_back(&self) -> Option<Back> {
+        let mut back = self.imp().back.borrow().clone()?;
+        back.tick = (self.report_position() * 10000000.0) as u64;
+        back.state = self.playback_state();
+        Some(back)
+    }
+
+    fn report_position(&self) -> f64 {
+        let imp = self.imp();
+        let position = imp.video.position();
+        // vo=gpu-next resets the position to 0 while the window closes, so fall back
+        // to the last position mpv actually played
+        if position > 0.0 {
+            imp.last_position.set(position);
+        }
+        imp.last_position.get()
+    }
+
+    /// Closes the playback session of the current video, returning the reports that
+    /// end it: the stopped report, and marking it played once past the played threshold.
+    fn end_session(&self) -> Vec<OutboxAction> {
+        let imp = self.imp();
+        let mut actions = Vec::new();
+        if let Some(mut back) = self.current_back() {
+            let position = self.report_position();
+            let duration = imp.video_scale.adjustment().upper();
+            // Too early to be worth resuming from, so clear the resume point instead
+            if !is_resumable_position(position) {
+                back.tick = 0;
+            }
+            let id = back.id.clone();
+            actions.push(OutboxAction::Playback(back, BackType::Stop));
+            if reached_played_threshold(position, duration) {
+                actions.push(OutboxAction::Played(id));
+            }
+        }
+        imp.back.take();
+        self.remove_timeout();
+        actions
+    }
+
+    /// Stops mpv, returning the reports that end the session it was playing.
+    fn stop_playback(&self) -> Vec<OutboxAction> {
+        self.stop_next_episode_countdown();
+        let actions = self.end_session();
+        self.imp().video_scale.remove_timeout();
+        let mpv = &self.imp().video.imp().mpv;
+        mpv.pause(true);
+        mpv.stop();
+        mpv.event_thread_alive
+            .store(PAUSED, std::sync::atomic::Ordering::SeqCst);
+        actions
+    }
+
+    pub fn is_reporting(&self) -> bool {
+        self.imp().back.borrow().is_some()
+    }
 
-        // close window when vo=gpu-next will set position to 0, so we need to ignore it
-        if position < &9.0 && backtype != BackType::Start {
+    /// Stops playback and waits briefly for the final stopped report to reach the
+    /// server. Used before the window closes or the account changes.
+    pub async fn shutdown(&self) {
+        let actions = self.stop_playback();
+        if actions.is_empty() {
             return;
         }
+        spawn_tokio(async move { OUTBOX.submit_within(actions, SHUTDOWN_REPORT_TIMEOUT).await })
+            .await;
+    }
+
+    fn playback_state(&self) -> PlaybackState {
+        let imp = self.imp();
+        let mpv = &imp.video.imp().mpv;
 
-        if let Some(back) = back.as_ref() {
-            let duration = *position as u64 * 10000000;
-            let mut back = back.clone();
-            back.tick = duration;
-            spawn(spawn_tokio(async move {
-                let _ = EMBY_CLIENT.position_back(&back, backtype).await;
-            }))
+        let direct_play = self.is_direct_play();
+        let (audio_stream_index, subtitle_stream_index) = mpv
+            .track_list()
+            .map(|tracks| {
+                let selected = |tracks: &[MpvTrack]| {
+                    tracks
+                        .iter()
+                        .find(|track| track.selected)
+                        .and_then(|track| emby_stream_index(track, direct_play))
+                };
+                (selected(&tracks.audio_tracks), selected(&tracks.sub_tracks))
+            })
+            .unwrap_or_default();
+
+        let episode_list = imp.current_episode_list.borrow();
+        let playlist_index = imp
+            .current_video
+            .borrow()
+            .as_ref()
+            .and_then(|current| {
+                episode_list
+                    .iter()
+                    .position(|item| item.id() == current.id())
+            })
+            .unwrap_or(0);
+
+        PlaybackState {
+            is_paused: mpv.paused(),
+            volume: mpv.volume(),
+            is_muted: mpv.muted(),
+            speed: mpv.speed(),
+            audio_stream_index,
+            subtitle_stream_index,
+            play_method: imp.play_method.get(),
+            playlist_index,
+            playlist_length: episode_list.len().max(1),
+            buffered_ranges: mpv
+                .seekable_ranges()
+                .into_iter()
+                .map(|(start, end)| ((start * 10000000.0) as u64, (end * 10000000.0) as u64))
+                .collect(),
         }
     }
 
@@ -789,11 +2422,94 @@ impl MPVPage {
     }
 
     pub fn key_pressed_cb(&self, key: u32, state: gtk::gdk::ModifierType) {
-        self.imp().video.press_key(key, state)
+        let imp = self.imp();
+        match self.bound_command(key, state) {
+            Some(BindingCommand::Action(name)) => {
+                if self.activate_action(&name, None).is_err() {
+                    warn!("Unknown player action: {}", name);
+                }
+            }
+            Some(BindingCommand::Mpv(command)) => imp.video.imp().mpv.run_command(&command),
+            None => imp.video.press_key(key, state),
+        }
     }
 
     pub fn key_released_cb(&self, key: u32, state: gtk::gdk::ModifierType) {
-        self.imp().video.release_key(key, state)
+        // Bound keys never reached mpv
+        if self.bound_command(key, state).is_none() {
+            self.imp().video.release_key(key, state)
+        }
+    }
+
+    fn bound_command(&self, key: u32, state: gtk::gdk::ModifierType) -> Option<BindingCommand> {
+        let keystr = get_full_keystr(key, state)?;
+        self.imp().keybindings.borrow().lookup(&keystr).cloned()
+    }
+
+    /// Picks up the keybindings saved in the settings.
+    pub fn reload_keybindings(&self) {
+        self.imp().keybindings.replace(Keybindings::load());
+    }
+
+    /// Saves the current frame into the screenshot folder and copies it to the clipboard.
+    pub fn screenshot(&self, subtitles: bool) {
+        let imp = self.imp();
+        if imp.current_video.borrow().is_none() {
+            return;
+        }
+
+        let dir = screenshot_dir();
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            warn!("Failed to create {}: {}", dir.display(), e);
+            toast!(self, gettext("Failed to save screenshot"));
+            return;
+        }
+        let path = screenshot_path(
+            &dir,
+            imp.current_video.borrow().as_ref(),
+            imp.video.position(),
+        );
+        if !imp
+            .video
+            .imp()
+            .mpv
+            .screenshot_to_file(&path.to_string_lossy(), subtitles)
+        {
+            toast!(self, gettext("Failed to save screenshot"));
+            return;
+        }
+
+        if SETTINGS.mpv_screenshot_copy() {
+            match gtk::gdk::Texture::from_filename(&path) {
+                Ok(texture) => self.clipboard().set_texture(&texture),
+                Err(e) => warn!("Failed to copy screenshot: {}", e),
+            }
+        }
+
+        let toast = adw::Toast::builder()
+            .timeout(3)
+            .use_markup(false)
+            .title(gettext("Screenshot saved"))
+            .button_label(gettext("Open"))
+            .build();
+        let file = gio::File::for_path(&path);
+        toast.connect_button_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                let window = obj.root().and_downcast::<gtk::Window>();
+                gtk::FileLauncher::new(Some(&file)).launch(
+                    window.as_ref(),
+                    None::<&gio::Cancellable>,
+                    |result| {
+                        if let Err(e) = result {
+                            warn!("Failed to open screenshot: {}", e);
+                        }
+                    },
+                );
+            }
+        ));
+        crate::_add_toast!(self, toast);
     }
 
     pub fn set_popover(&self) {
@@ -825,12 +2541,17 @@ impl MPVPage {
         video.seek_forward(SETTINGS.mpv_seek_forward_step() as i64)
     }
 
+    // Straight to mpv, these keys may be bound to the chapter actions themselves
     pub fn chapter_prev(&self) {
-        self.key_pressed_cb(PREV_CHAPTER_KEYVAL, gtk::gdk::ModifierType::empty());
+        self.imp()
+            .video
+            .press_key(PREV_CHAPTER_KEYVAL, gtk::gdk::ModifierType::empty());
     }
 
     pub fn chapter_next(&self) {
-        self.key_pressed_cb(NEXT_CHAPTER_KEYVAL, gtk::gdk::ModifierType::empty());
+        self.imp()
+            .video
+            .press_key(NEXT_CHAPTER_KEYVAL, gtk::gdk::ModifierType::empty());
     }
 
     pub fn load_config(&self) {
@@ -839,6 +2560,7 @@ impl MPVPage {
             .set_visible(SETTINGS.mpv_show_buffer_speed());
         imp.volume_adj
             .set_value(SETTINGS.mpv_default_volume() as f64);
+        self.set_exact_seek(SETTINGS.mpv_exact_seek());
         let mpv = &imp.video.imp().mpv;
         if !SETTINGS.proxy().is_empty() {
             mpv.set_property("http-proxy", SETTINGS.proxy());
@@ -875,20 +2597,9 @@ impl MPVPage {
             2 => mpv.set_property("vo", "dmabuf-wayland"),
             _ => unreachable!(),
         }
-        match SETTINGS.mpv_audio_preferred_lang() {
-            0 => mpv.set_property("alang", ""), // clear alang
-            1 => mpv.set_property("alang", "eng"),
-            2 => mpv.set_property("alang", "chs"),
-            3 => mpv.set_property("alang", "jpn"),
-            _ => unreachable!(),
-        }
-        match SETTINGS.mpv_subtitle_preferred_lang() {
-            0 => mpv.set_property("slang", ""), // clear slang
-            1 => mpv.set_property("slang", "eng"),
-            2 => mpv.set_property("slang", "chs"),
-            3 => mpv.set_property("slang", "jpn"),
-            _ => unreachable!(),
-        }
+        // mpv takes the lists in order of preference, the rules refine them per file
+        mpv.set_property("alang", SETTINGS.mpv_audio_lang_priority().join(","));
+        mpv.set_property("slang", SETTINGS.mpv_subtitle_lang_priority().join(","));
         if SETTINGS.mpv_action_after_video_end() == 1 {
             mpv.set_property("loop", "inf");
         } else {
@@ -896,3 +2607,144 @@ impl MPVPage {
         }
     }
 }
+
+fn chapter_row(index: usize, chapter: &Chapter, item_id: Option<&str>) -> gtk::ListBoxRow {
+    let content = gtk::Box::builder()
+        .spacing(12)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+    if let (Some(item_id), Some(image_index)) = (item_id, chapter.image_index) {
+        let picture = PictureLoader::new(item_id, "Chapter", Some(image_index.to_string()));
+        picture.set_size_request(128, 72);
+        content.append(&picture);
+    }
+
+    let labels = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .valign(gtk::Align::Center)
+        .build();
+    let title = chapter
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("{} {}", gettext("Chapter"), index + 1));
+    labels.append(
+        &gtk::Label::builder()
+            .label(title)
+            .xalign(0.0)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build(),
+    );
+    labels.append(
+        &gtk::Label::builder()
+            .label(format_duration(chapter.start as i64))
+            .xalign(0.0)
+            .css_classes(["dim-label", "numeric"])
+            .build(),
+    );
+    content.append(&labels);
+
+    gtk::ListBoxRow::builder().child(&content).build()
+}
+
+fn submit_reports(actions: Vec<OutboxAction>) {
+    if actions.is_empty() {
+        return;
+    }
+    spawn(spawn_tokio(async move {
+        for action in actions {
+            if let Err(e) = OUTBOX.submit(action).await {
+                warn!("Failed to report playback: {}", e);
+            }
+        }
+    }))
+}
+
+/// Maps an mpv track back to the index of the Emby media stream it was loaded from.
+/// Streams remuxed or transcoded by the server number their tracks differently, so
+/// embedded tracks are only mapped in `direct_play`.
+fn emby_stream_index(track: &MpvTrack, direct_play: bool) -> Option<i64> {
+    // External subtitles are delivered from `.../Subtitles/{index}/Stream.{ext}`
+    if let Some(filename) = &track.external_filename {
+        return filename
+            .split('/')
+            .skip_while(|segment| !segment.eq_ignore_ascii_case("Subtitles"))
+            .nth(1)
+            .and_then(|index| index.parse().ok());
+    }
+    track.ff_index.filter(|_| direct_play)
+}
+
+// Seconds in a time written as `h:mm:ss`, `m:ss` or `s`, each part may have a fraction.
+// Only the first part may go past 59.
+fn parse_time(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text.is_empty() || text.split(':').count() > 3 {
+        return None;
+    }
+    text.split(':')
+        .enumerate()
+        .try_fold(0.0, |seconds, (index, part)| {
+            let part = part.trim().parse::<f64>().ok()?;
+            let in_range = part.is_finite() && part >= 0.0 && (index == 0 || part < 60.0);
+            in_range.then_some(seconds * 60.0 + part)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{emby_stream_index, parse_time};
+    use crate::ui::mpv::tsukimi_mpv::MpvTrack;
+
+    fn track(ff_index: Option<i64>, external_filename: Option<&str>) -> MpvTrack {
+        MpvTrack {
+            id: 1,
+            title: String::new(),
+            lang: String::new(),
+            type_: String::new(),
+            selected: true,
+            forced: false,
+            hearing_impaired: false,
+            ff_index,
+            external_filename: external_filename.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn embedded_tracks_only_mapped_in_direct_play() {
+        assert_eq!(emby_stream_index(&track(Some(2), None), true), Some(2));
+        assert_eq!(emby_stream_index(&track(Some(2), None), false), None);
+    }
+
+    #[test]
+    fn external_subtitles_mapped_by_url() {
+        let url = "http://emby/Videos/1/abc/Subtitles/5/Stream.srt?api_key=x";
+        assert_eq!(emby_stream_index(&track(None, Some(url)), false), Some(5));
+        assert_eq!(emby_stream_index(&track(None, Some(url)), true), Some(5));
+    }
+
+    #[test]
+    fn parse_time_formats() {
+        assert_eq!(parse_time("42"), Some(42.0));
+        assert_eq!(parse_time("1:30"), Some(90.0));
+        assert_eq!(parse_time("1:02:03"), Some(3723.0));
+        assert_eq!(parse_time(" 0:05.5 "), Some(5.5));
+        assert_eq!(parse_time("90:00"), Some(5400.0));
+    }
+
+    #[test]
+    fn parse_time_out_of_range() {
+        assert_eq!(parse_time("1:90"), None);
+        assert_eq!(parse_time("1:60:00"), None);
+        assert_eq!(parse_time("-5"), None);
+        assert_eq!(parse_time("inf"), None);
+    }
+
+    #[test]
+    fn parse_time_invalid() {
+        assert_eq!(parse_time(""), None);
+        assert_eq!(parse_time("1:2:3:4"), None);
+        assert_eq!(parse_time("1::30"), None);
+        assert_eq!(parse_time("a:30"), None);
+    }
+}
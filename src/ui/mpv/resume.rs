@@ -0,0 +1,65 @@
+use adw::prelude::*;
+use gettextrs::gettext;
+
+use crate::ui::models::SETTINGS;
+use crate::ui::provider::tu_item::TuItem;
+use crate::ui::widgets::song_widget::format_duration;
+
+const TICKS_PER_SECOND: f64 = 10000000.0;
+
+/// Where playback of `item` should start, as a percentage of its runtime.
+///
+/// Resume points before the minimum resume position are ignored, and the user is asked
+/// first when the resume prompt is enabled. Returns `None` if the prompt is dismissed.
+pub async fn start_percentage(parent: &impl IsA<gtk::Widget>, item: &TuItem) -> Option<f64> {
+    let percentage = item.played_percentage();
+    let position = resume_position(item);
+
+    if percentage <= 0.0 || position < SETTINGS.mpv_resume_min_position() as f64 {
+        return Some(0.0);
+    }
+
+    if !SETTINGS.mpv_resume_prompt() {
+        return Some(percentage);
+    }
+
+    let dialog = adw::AlertDialog::new(Some(&gettext("Resume Playback")), Some(&item.name()));
+    let resume_label = format!(
+        "{} {}",
+        gettext("Resume from"),
+        format_duration(position as i64)
+    );
+    dialog.add_responses(&[
+        ("cancel", gettext("Cancel").as_str()),
+        ("start-over", gettext("Start Over").as_str()),
+        ("resume", resume_label.as_str()),
+    ]);
+    dialog.set_response_appearance("resume", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("resume"));
+    dialog.set_close_response("cancel");
+
+    match dialog.choose_future(Some(parent)).await.as_str() {
+        "resume" => Some(percentage),
+        "start-over" => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Resume position of `item` in seconds.
+fn resume_position(item: &TuItem) -> f64 {
+    let ticks = item.playback_position_ticks();
+    if ticks > 0 {
+        return ticks as f64 / TICKS_PER_SECOND;
+    }
+    item.run_time_ticks() as f64 / TICKS_PER_SECOND * item.played_percentage() / 100.0
+}
+
+/// Whether stopping at `position` out of `duration` seconds counts as watched.
+pub fn reached_played_threshold(position: f64, duration: f64) -> bool {
+    duration > 0.0 && position / duration * 100.0 >= SETTINGS.mpv_played_threshold() as f64
+}
+
+/// Whether `position` is far enough in to be worth saving as a resume point.
+pub fn is_resumable_position(position: f64) -> bool {
+    position >= SETTINGS.mpv_resume_min_position() as f64
+}
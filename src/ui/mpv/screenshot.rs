@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+use gtk::glib;
+
+use crate::ui::models::SETTINGS;
+use crate::ui::provider::tu_item::TuItem;
+
+/// Where screenshots are saved, the folder picked in the settings or `Pictures/Tsukimi`.
+pub fn screenshot_dir() -> PathBuf {
+    let dir = SETTINGS.mpv_screenshot_dir();
+    if !dir.is_empty() {
+        return PathBuf::from(dir);
+    }
+    glib::user_special_dir(glib::UserDirectory::Pictures)
+        .unwrap_or_else(glib::home_dir)
+        .join("Tsukimi")
+}
+
+/// A free path in `dir` for a screenshot of `item` at `position` seconds, named like
+/// `Series - S01E02 - 00.12.34.png`.
+pub fn screenshot_path(dir: &Path, item: Option<&TuItem>, position: f64) -> PathBuf {
+    let title = match item {
+        Some(item) => match item.series_name() {
+            Some(series_name) => format!(
+                "{} - S{:02}E{:02}",
+                series_name,
+                item.parent_index_number(),
+                item.index_number()
+            ),
+            None => item.name(),
+        },
+        None => "Tsukimi".to_string(),
+    };
+    let position = position.max(0.0) as u64;
+    let stem = format!(
+        "{} - {:02}.{:02}.{:02}",
+        sanitize(&title),
+        position / 3600,
+        position % 3600 / 60,
+        position % 60
+    );
+
+    let mut path = dir.join(format!("{}.png", stem));
+    let mut index = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).png", stem, index));
+        index += 1;
+    }
+    path
+}
+
+// Characters some file systems don't allow in names
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+use gtk::gdk;
+use serde::{Deserialize, Serialize};
+
+use super::tsukimi_mpv::TsukimiMPV;
+
+/// Values of `sub-ass-override`, in the order the style panel lists them.
+pub const ASS_OVERRIDE_MODES: &[&str] = &["no", "yes", "scale", "force", "strip"];
+
+/// How subtitles are timed, placed and drawn, as set in the player's subtitle panel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SubtitleStyle {
+    /// Seconds
+    pub delay: f64,
+    /// Percent of the screen height, 100 is the bottom
+    pub position: f64,
+    pub scale: f64,
+    /// mpv colour, as `r/g/b/a`
+    pub color: String,
+    pub outline_size: f64,
+    /// Draw a box behind the text instead of an outline
+    pub background: bool,
+    /// Index into [`ASS_OVERRIDE_MODES`]
+    pub ass_override: u32,
+}
+
+impl Default for SubtitleStyle {
+    // mpv's own defaults
+    fn default() -> Self {
+        Self {
+            delay: 0.0,
+            position: 100.0,
+            scale: 1.0,
+            color: "1.0/1.0/1.0/1.0".to_string(),
+            outline_size: 3.0,
+            background: false,
+            ass_override: 2,
+        }
+    }
+}
+
+impl SubtitleStyle {
+    /// The style mpv uses right now, which is the one of mpv.conf until another is applied.
+    pub fn from_mpv(mpv: &TsukimiMPV) -> Self {
+        let default = Self::default();
+        let ass_override = mpv
+            .get_property::<String>("sub-ass-override")
+            .and_then(|mode| ASS_OVERRIDE_MODES.iter().position(|m| *m == mode))
+            .map_or(default.ass_override, |index| index as u32);
+        let color = mpv
+            .get_property::<String>("sub-color")
+            .and_then(|color| argb_to_color(&color))
+            .unwrap_or(default.color);
+        Self {
+            delay: mpv.get_property("sub-delay").unwrap_or(default.delay),
+            position: mpv.get_property("sub-pos").unwrap_or(default.position),
+            scale: mpv.get_property("sub-scale").unwrap_or(default.scale),
+            color,
+            outline_size: mpv
+                .get_property("sub-border-size")
+                .unwrap_or(default.outline_size),
+            background: mpv
+                .get_property::<String>("sub-border-style")
+                .is_some_and(|style| style == "background-box"),
+            ass_override,
+        }
+    }
+
+    pub fn apply(&self, mpv: &TsukimiMPV) {
+        mpv.set_property("sub-delay", self.delay);
+        mpv.set_property("sub-pos", self.position);
+        mpv.set_property("sub-scale", self.scale);
+        mpv.set_property("sub-color", self.color.as_str());
+        mpv.set_property("sub-border-size", self.outline_size);
+        mpv.set_property(
+            "sub-border-style",
+            if self.background {
+                "background-box"
+            } else {
+                "outline-and-shadow"
+            },
+        );
+        let ass_override = ASS_OVERRIDE_MODES
+            .get(self.ass_override as usize)
+            .unwrap_or(&"scale");
+        mpv.set_property("sub-ass-override", *ass_override);
+    }
+
+    pub fn rgba(&self) -> gdk::RGBA {
+        let mut channels = self
+            .color
+            .split('/')
+            .map(|channel| channel.parse::<f32>().unwrap_or(1.0));
+        let mut next = || channels.next().unwrap_or(1.0);
+        gdk::RGBA::new(next(), next(), next(), next())
+    }
+
+    pub fn set_rgba(&mut self, rgba: &gdk::RGBA) {
+        self.color = format!(
+            "{:.3}/{:.3}/{:.3}/{:.3}",
+            rgba.red(),
+            rgba.green(),
+            rgba.blue(),
+            rgba.alpha()
+        );
+    }
+}
+
+/// Turns mpv's `#AARRGGBB` colours into `r/g/b/a`.
+fn argb_to_color(argb: &str) -> Option<String> {
+    let hex = argb.strip_prefix('#')?;
+    if hex.len() != 8 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|value| value as f32 / 255.0)
+    };
+    let (a, r, g, b) = (channel(0)?, channel(2)?, channel(4)?, channel(6)?);
+    Some(format!("{:.3}/{:.3}/{:.3}/{:.3}", r, g, b, a))
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::client::client::EMBY_CLIENT;
+use crate::ui::models::emby_data_path;
+
+use super::subtitle_style::SubtitleStyle;
+use super::tsukimi_mpv::MpvTrack;
+
+/// The parts of a picked track that carry over from one episode to the next.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackChoice {
+    pub lang: String,
+    pub title: String,
+    #[serde(default)]
+    pub forced: bool,
+    #[serde(default)]
+    pub hearing_impaired: bool,
+}
+
+impl TrackChoice {
+    pub fn from_track(track: &MpvTrack) -> Self {
+        Self {
+            lang: track.lang.clone(),
+            title: track.title.clone(),
+            forced: track.forced,
+            hearing_impaired: track.hearing_impaired,
+        }
+    }
+
+    /// The track of `tracks` closest to this choice. Only tracks in the same language
+    /// qualify, then forced and SDH flags and the words of the title decide.
+    pub fn best_match<'a>(&self, tracks: &'a [MpvTrack]) -> Option<&'a MpvTrack> {
+        tracks
+            .iter()
+            .filter_map(|track| Some((self.score(track)?, track)))
+            .rev()
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, track)| track)
+    }
+
+    fn score(&self, track: &MpvTrack) -> Option<usize> {
+        if !self.lang.eq_ignore_ascii_case(&track.lang) {
+            return None;
+        }
+        let words = title_words(&track.title);
+        let shared = title_words(&self.title)
+            .iter()
+            .filter(|word| words.contains(word))
+            .count();
+        // Without a language the title is all there is to go by
+        if self.lang == "unknown" && shared == 0 {
+            return None;
+        }
+
+        let mut score = shared;
+        if self.forced == track.forced {
+            score += 100;
+        }
+        if self.hearing_impaired == track.hearing_impaired {
+            score += 50;
+        }
+        Some(score)
+    }
+}
+
+fn title_words(title: &str) -> Vec<String> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SubtitleChoice {
+    Off,
+    Track(TrackChoice),
+}
+
+/// Remembered tracks, `None` where nothing was picked yet.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TrackPreference {
+    pub audio: Option<TrackChoice>,
+    pub subtitle: Option<SubtitleChoice>,
+}
+
+/// Track choices per series, and per library as the default for series without one.
+/// Subtitle styles and intro skipping are only kept per series.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct TrackMemory {
+    #[serde(default)]
+    series: HashMap<String, TrackPreference>,
+    #[serde(default)]
+    libraries: HashMap<String, TrackPreference>,
+    #[serde(default)]
+    subtitle_styles: HashMap<String, SubtitleStyle>,
+    #[serde(default)]
+    skip_intro_series: HashSet<String>,
+}
+
+impl TrackMemory {
+    fn path() -> PathBuf {
+        emby_data_path().join(format!(
+            "tracks-{}.json",
+            EMBY_CLIENT.user_id.lock().unwrap()
+        ))
+    }
+
+    fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_string(self)
+            .map_err(std::io::Error::other)
+            .and_then(|serialized| std::fs::write(Self::path(), serialized));
+        if let Err(e) = result {
+            warn!("Failed to save track memory: {}", e);
+        }
+    }
+}
+
+/// Tracks to pick for a video of `series_id` in `library_id`. Choices made for the series
+/// win over the library default.
+pub fn track_preference(series_id: Option<&str>, library_id: Option<&str>) -> TrackPreference {
+    let memory = TrackMemory::load();
+    let series = series_id.and_then(|id| memory.series.get(id));
+    let library = library_id.and_then(|id| memory.libraries.get(id));
+    TrackPreference {
+        audio: series
+            .and_then(|preference| preference.audio.clone())
+            .or_else(|| library.and_then(|preference| preference.audio.clone())),
+        subtitle: series
+            .and_then(|preference| preference.subtitle.clone())
+            .or_else(|| library.and_then(|preference| preference.subtitle.clone())),
+    }
+}
+
+/// Records a track picked while watching `series_id` in `library_id`, for the series and
+/// as the new library default.
+pub fn remember_tracks<F>(series_id: Option<&str>, library_id: Option<&str>, update: F)
+where
+    F: Fn(&mut TrackPreference),
+{
+    if series_id.is_none() && library_id.is_none() {
+        return;
+    }
+    let mut memory = TrackMemory::load();
+    if let Some(id) = series_id {
+        update(memory.series.entry(id.to_string()).or_default());
+    }
+    if let Some(id) = library_id {
+        update(memory.libraries.entry(id.to_string()).or_default());
+    }
+    memory.save();
+}
+
+/// The subtitle style saved for `series_id`, if any.
+pub fn subtitle_style(series_id: &str) -> Option<SubtitleStyle> {
+    TrackMemory::load().subtitle_styles.remove(series_id)
+}
+
+/// Saves `style` for `series_id`. Saving the default style forgets the series.
+pub fn remember_subtitle_style(series_id: &str, style: &SubtitleStyle) {
+    let mut memory = TrackMemory::load();
+    if *style == SubtitleStyle::default() {
+        memory.subtitle_styles.remove(series_id);
+    } else {
+        memory
+            .subtitle_styles
+            .insert(series_id.to_string(), style.clone());
+    }
+    memory.save();
+}
+
+/// Whether intros of `series_id` are skipped without asking.
+pub fn skips_intro(series_id: &str) -> bool {
+    TrackMemory::load().skip_intro_series.contains(series_id)
+}
+
+pub fn set_skips_intro(series_id: &str, skip: bool) {
+    let mut memory = TrackMemory::load();
+    let changed = if skip {
+        memory.skip_intro_series.insert(series_id.to_string())
+    } else {
+        memory.skip_intro_series.remove(series_id)
+    };
+    if changed {
+        memory.save();
+    }
+}
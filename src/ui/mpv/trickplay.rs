@@ -0,0 +1,320 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::{gdk, gio, glib};
+use tracing::{debug, warn};
+
+use crate::client::client::EMBY_CLIENT;
+use crate::client::structs::TrickplayInfo;
+use crate::ui::models::emby_cache_path;
+use crate::utils::spawn_tokio;
+
+const BIF_MAGIC: &[u8] = &[0x89, 0x42, 0x49, 0x46, 0x0d, 0x0a, 0x1a, 0x0a];
+const BIF_INDEX_OFFSET: usize = 64;
+// Width the previews are fetched at, the closest one is used for tile sheets
+const PREFERRED_WIDTH: u32 = 320;
+
+/// Seek bar previews of a video.
+pub enum Trickplay {
+    /// Emby's BIF container, a JPEG per thumbnail
+    Bif(Bif),
+    /// Jellyfin's tile sheets, a grid of thumbnails per image
+    Tiles(Tiles),
+}
+
+impl Trickplay {
+    /// The thumbnail shown for `position`, in seconds.
+    pub fn thumbnail(&self, position: f64) -> Option<gdk::Texture> {
+        match self {
+            Trickplay::Bif(bif) => bif.thumbnail(position),
+            Trickplay::Tiles(tiles) => tiles.thumbnail(position),
+        }
+    }
+}
+
+pub struct Bif {
+    data: Vec<u8>,
+    /// Start in seconds and the bytes of each thumbnail
+    frames: Vec<(f64, Range<usize>)>,
+}
+
+impl Bif {
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        let read_u32 = |offset: usize| -> Result<u32> {
+            let bytes = data
+                .get(offset..offset + 4)
+                .ok_or_else(|| anyhow!("BIF is truncated"))?;
+            Ok(u32::from_le_bytes(bytes.try_into()?))
+        };
+
+        if !data.starts_with(BIF_MAGIC) {
+            return Err(anyhow!("Not a BIF file"));
+        }
+        let count = read_u32(12)? as usize;
+        let multiplier = match read_u32(16)? {
+            0 => 1000,
+            multiplier => multiplier,
+        };
+
+        // Each entry is a timestamp and an offset, the last one only marks the end
+        let mut frames = Vec::with_capacity(count);
+        for index in 0..count {
+            let entry = BIF_INDEX_OFFSET + index * 8;
+            let timestamp = read_u32(entry)?;
+            let start = read_u32(entry + 4)? as usize;
+            let end = read_u32(entry + 12)? as usize;
+            if start > end || end > data.len() {
+                return Err(anyhow!("BIF index points outside the file"));
+            }
+            let seconds = timestamp as f64 * multiplier as f64 / 1000.0;
+            frames.push((seconds, start..end));
+        }
+
+        Ok(Self { data, frames })
+    }
+
+    fn thumbnail(&self, position: f64) -> Option<gdk::Texture> {
+        let index = self
+            .frames
+            .iter()
+            .rposition(|(start, _)| *start <= position)?;
+        let bytes = glib::Bytes::from(&self.data[self.frames[index].1.clone()]);
+        gdk::Texture::from_bytes(&bytes).ok()
+    }
+}
+
+pub struct Tiles {
+    info: TrickplayInfo,
+    /// The JPEG of each sheet
+    sheets: Vec<Option<glib::Bytes>>,
+    /// The last sheet hovered, decoded, since the next thumbnail is likely on it too
+    decoded: RefCell<Option<(usize, Pixbuf)>>,
+}
+
+impl Tiles {
+    fn sheet(&self, index: usize) -> Option<Pixbuf> {
+        if let Some((decoded_index, sheet)) = self.decoded.borrow().as_ref() {
+            if *decoded_index == index {
+                return Some(sheet.clone());
+            }
+        }
+        let bytes = self.sheets.get(index)?.as_ref()?;
+        let stream = gio::MemoryInputStream::from_bytes(bytes);
+        let sheet = Pixbuf::from_stream(&stream, gio::Cancellable::NONE)
+            .map_err(|e| warn!("Failed to decode trickplay sheet {}: {}", index, e))
+            .ok()?;
+        self.decoded.replace(Some((index, sheet.clone())));
+        Some(sheet)
+    }
+
+    fn thumbnail(&self, position: f64) -> Option<gdk::Texture> {
+        let info = &self.info;
+        if info.interval == 0 || info.thumbnail_count == 0 {
+            return None;
+        }
+        let index =
+            ((position * 1000.0 / info.interval as f64) as u32).min(info.thumbnail_count - 1);
+        let per_sheet = info.tile_width * info.tile_height;
+        let sheet = self.sheet((index / per_sheet) as usize)?;
+        let tile = index % per_sheet;
+        let x = (tile % info.tile_width * info.width) as i32;
+        let y = (tile / info.tile_width * info.height) as i32;
+        // The last thumbnails of a sheet may be cut short
+        let width = (info.width as i32).min(sheet.width() - x);
+        let height = (info.height as i32).min(sheet.height() - y);
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        Some(gdk::Texture::for_pixbuf(
+            &sheet.new_subpixbuf(x, y, width, height),
+        ))
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    emby_cache_path().join("trickplay")
+}
+
+/// Fetches the previews of `media_source_id` of item `id`, tile sheets if the server
+/// makes them and a BIF otherwise. Downloads are kept in the cache for the next time the
+/// item plays.
+pub async fn fetch(id: &str, media_source_id: &str) -> Option<Trickplay> {
+    match fetch_tiles(id, media_source_id).await {
+        Ok(Some(tiles)) => return Some(Trickplay::Tiles(tiles)),
+        Ok(None) => {}
+        Err(e) => debug!("No trickplay tiles for {}: {}", id, e),
+    }
+    match fetch_bif(id, media_source_id).await {
+        Ok(bif) => Some(Trickplay::Bif(bif)),
+        Err(e) => {
+            debug!("No BIF for {}: {}", id, e);
+            None
+        }
+    }
+}
+
+async fn fetch_bif(id: &str, media_source_id: &str) -> Result<Bif> {
+    let path = cache_dir().join(format!("{}-{}.bif", id, media_source_id));
+    let id = id.to_string();
+    let media_source_id = media_source_id.to_string();
+    let data = spawn_tokio(async move {
+        if let Ok(data) = std::fs::read(&path) {
+            return Ok(data);
+        }
+        let data = EMBY_CLIENT.get_bif(&id, &media_source_id).await?;
+        cache_file(&path, &data);
+        Ok::<_, anyhow::Error>(data)
+    })
+    .await?;
+    Bif::parse(data)
+}
+
+async fn fetch_tiles(id: &str, media_source_id: &str) -> Result<Option<Tiles>> {
+    let item_id = id.to_string();
+    let resolutions =
+        spawn_tokio(async move { EMBY_CLIENT.get_trickplay_info(&item_id).await }).await?;
+    let Some(info) = resolutions
+        .get(media_source_id)
+        .or_else(|| resolutions.values().next())
+        .and_then(|resolutions| {
+            resolutions
+                .values()
+                .min_by_key(|info| info.width.abs_diff(PREFERRED_WIDTH))
+        })
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    let per_sheet = info.tile_width * info.tile_height;
+    if per_sheet == 0 {
+        return Ok(None);
+    }
+
+    let sheet_count = info.thumbnail_count.div_ceil(per_sheet);
+    let id = id.to_string();
+    let media_source_id = media_source_id.to_string();
+    let width = info.width;
+    let sheets = spawn_tokio(async move {
+        let mut sheets = Vec::new();
+        for index in 0..sheet_count {
+            let path = cache_dir().join(format!(
+                "{}-{}-{}-{}.jpg",
+                id, media_source_id, width, index
+            ));
+            let data = match std::fs::read(&path) {
+                Ok(data) => Some(data),
+                Err(_) => match EMBY_CLIENT
+                    .get_trickplay_tile(&id, &media_source_id, width, index)
+                    .await
+                {
+                    Ok(data) => {
+                        cache_file(&path, &data);
+                        Some(data)
+                    }
+                    Err(e) => {
+                        warn!("Failed to download trickplay sheet {}: {}", index, e);
+                        None
+                    }
+                },
+            };
+            sheets.push(data.map(glib::Bytes::from_owned));
+        }
+        sheets
+    })
+    .await;
+
+    Ok(Some(Tiles {
+        info,
+        sheets,
+        decoded: RefCell::new(None),
+    }))
+}
+
+fn cache_file(path: &Path, data: &[u8]) {
+    let result = std::fs::create_dir_all(cache_dir()).and_then(|_| std::fs::write(path, data));
+    if let Err(e) = result {
+        warn!("Failed to cache trickplay data: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A BIF with `images`, each shown from the given timestamp on.
+    fn bif(multiplier: u32, images: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut data = BIF_MAGIC.to_vec();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(images.len() as u32).to_le_bytes());
+        data.extend_from_slice(&multiplier.to_le_bytes());
+        data.resize(BIF_INDEX_OFFSET, 0);
+
+        let mut offset = BIF_INDEX_OFFSET + (images.len() + 1) * 8;
+        for (timestamp, image) in images {
+            data.extend_from_slice(&timestamp.to_le_bytes());
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += image.len();
+        }
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        for (_, image) in images {
+            data.extend_from_slice(image);
+        }
+        data
+    }
+
+    fn set_offset(data: &mut [u8], index: usize, offset: u32) {
+        let entry = BIF_INDEX_OFFSET + index * 8 + 4;
+        data[entry..entry + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    #[test]
+    fn parse_frames() {
+        let data = bif(0, &[(0, b"first"), (10, b"second")]);
+        let bif = Bif::parse(data).unwrap();
+        let frames = bif
+            .frames
+            .iter()
+            .map(|(start, range)| (*start, &bif.data[range.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(frames, [(0.0, &b"first"[..]), (10.0, &b"second"[..])]);
+    }
+
+    #[test]
+    fn parse_multiplier() {
+        let bif = Bif::parse(bif(500, &[(0, b"a"), (3, b"b")])).unwrap();
+        assert_eq!(bif.frames[1].0, 1.5);
+    }
+
+    #[test]
+    fn parse_not_bif() {
+        let mut data = bif(0, &[(0, b"a")]);
+        data[1] = b'X';
+        assert!(Bif::parse(data).is_err());
+    }
+
+    #[test]
+    fn parse_truncated_index() {
+        let data = bif(0, &[(0, b"a"), (10, b"b")]);
+        assert!(Bif::parse(data[..BIF_INDEX_OFFSET + 12].to_vec()).is_err());
+        assert!(Bif::parse(data[..16].to_vec()).is_err());
+    }
+
+    #[test]
+    fn parse_truncated_images() {
+        let data = bif(0, &[(0, b"first"), (10, b"second")]);
+        assert!(Bif::parse(data[..data.len() - 1].to_vec()).is_err());
+    }
+
+    #[test]
+    fn parse_overlapping_index() {
+        let mut data = bif(0, &[(0, b"first"), (10, b"second")]);
+        let first = BIF_INDEX_OFFSET as u32 + 3 * 8;
+        // The second image starting before the first one
+        set_offset(&mut data, 1, first - 1);
+        assert!(Bif::parse(data).is_err());
+    }
+}
@@ -7,6 +7,8 @@ use libmpv2::{
 use tokio::time;
 use tracing::{info, warn};
 
+use crate::client::device_profile::{set_player_capabilities, PlayerCapabilities};
+
 use std::{
     cell::RefCell,
     collections::HashMap,
@@ -18,12 +20,25 @@ use libmpv2::{
     Mpv,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MpvTrack {
     pub id: i64,
     pub title: String,
     pub lang: String,
     pub type_: String,
+    pub selected: bool,
+    pub forced: bool,
+    pub hearing_impaired: bool,
+    /// Stream index inside the container, absent for external tracks
+    pub ff_index: Option<i64>,
+    pub external_filename: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MpvChapter {
+    pub title: Option<String>,
+    /// Seconds
+    pub time: f64,
 }
 
 pub struct TsukimiMPV {
@@ -49,6 +64,19 @@ impl std::fmt::Display for TrackSelection {
 
 pub const PAUSED: u32 = 0;
 pub const ACTIVE: u32 = 1;
+// MPV_END_FILE_REASON_ERROR
+pub const END_FILE_REASON_ERROR: u32 = 4;
+
+// Errors mpv puts in an END_FILE event when the file couldn't be played, unlike the ones of
+// failed property and command replies. libmpv2 hands them over without the event.
+const END_FILE_ERRORS: &[libmpv2::MpvError] = &[
+    libmpv2::mpv_error::LoadingFailed,
+    libmpv2::mpv_error::NothingToPlay,
+    libmpv2::mpv_error::UnknownFormat,
+    libmpv2::mpv_error::Unsupported,
+    libmpv2::mpv_error::AoInitFailed,
+    libmpv2::mpv_error::VoInitFailed,
+];
 
 impl Default for TsukimiMPV {
     fn default() -> Self {
@@ -86,11 +114,13 @@ impl Default for TsukimiMPV {
         })
         .expect("Failed to create mpv instance");
 
-        Self {
+        let mpv = Self {
             mpv: RefCell::new(mpv),
             ctx: RefCell::new(None),
             event_thread_alive: Arc::new(AtomicU32::new(PAUSED)),
-        }
+        };
+        set_player_capabilities(mpv.capabilities());
+        mpv
     }
 }
 
@@ -133,6 +163,9 @@ pub enum ListenEvent {
     Volume(i64),
     Speed(f64),
     PausedForCache(bool),
+    SubDelay(f64),
+    ChapterList(Vec<MpvChapter>),
+    Chapter(i64),
 }
 
 pub static MPV_EVENT_CHANNEL: Lazy<MPVEventChannel> = Lazy::new(|| {
@@ -187,16 +220,97 @@ impl TsukimiMPV {
         self.set_property("pause", pause);
     }
 
+    pub fn volume(&self) -> i64 {
+        self.get_property::<f64>("volume").unwrap_or(100.0) as i64
+    }
+
+    pub fn muted(&self) -> bool {
+        self.get_property("mute").unwrap_or(false)
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.get_property("speed").unwrap_or(1.0)
+    }
+
+    pub fn track_list(&self) -> Option<MpvTracks> {
+        self.get_property::<MpvNode>("track-list")
+            .map(node_to_tracks)
+    }
+
+    /// Ranges of the demuxer cache that can be seeked to without hitting the network, in seconds.
+    pub fn seekable_ranges(&self) -> Vec<(f64, f64)> {
+        let Some(node) = self.get_property::<MpvNode>("demuxer-cache-state") else {
+            return Vec::new();
+        };
+        let Some(mut state) = node.map().map(|state| state.collect::<HashMap<_, _>>()) else {
+            return Vec::new();
+        };
+        let Some(ranges) = state.remove("seekable-ranges") else {
+            return Vec::new();
+        };
+        let Some(ranges) = ranges.array() else {
+            return Vec::new();
+        };
+        ranges
+            .filter_map(|range| {
+                let range = range.map()?.collect::<HashMap<_, _>>();
+                Some((range.get("start")?.f64()?, range.get("end")?.f64()?))
+            })
+            .collect()
+    }
+
+    /// Decoders and lavf demuxers this mpv build was compiled with.
+    pub fn capabilities(&self) -> PlayerCapabilities {
+        let decoders = self
+            .get_property::<MpvNode>("decoder-list")
+            .and_then(|node| {
+                let decoders = node
+                    .array()?
+                    .filter_map(|decoder| {
+                        let decoder = decoder.map()?.collect::<HashMap<_, _>>();
+                        decoder.get("codec")?.str().map(|codec| codec.to_string())
+                    })
+                    .collect();
+                Some(decoders)
+            })
+            .unwrap_or_default();
+        let demuxers = self
+            .get_property::<MpvNode>("demuxer-lavf-list")
+            .and_then(|node| {
+                let demuxers = node
+                    .array()?
+                    .filter_map(|demuxer| demuxer.str().map(|name| name.to_string()))
+                    .collect();
+                Some(demuxers)
+            })
+            .unwrap_or_default();
+        PlayerCapabilities { decoders, demuxers }
+    }
+
     pub fn add_sub(&self, url: &str) {
         self.command("sub-add", &[url, "select"]);
     }
 
+    /// Adds a subtitle track without selecting it, returns whether mpv could load it.
+    pub fn add_sub_track(&self, url: &str, title: &str, lang: Option<&str>) -> bool {
+        let args = sub_add_args(url, title, lang);
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let mpv = self.mpv.borrow();
+        mpv.command("sub-add", &args)
+            .map_err(|e| warn!("MPV sub-add Error: {}, Subtitle: {}", e, url))
+            .is_ok()
+    }
+
     pub fn load_video(&self, url: &str) {
         self.command("loadfile", &[url, "replace"]);
     }
 
     pub fn set_start(&self, percentage: f64) {
-        self.set_property("start", format!("{}%", percentage as u32));
+        self.set_property("start", format!("{}%", percentage));
+    }
+
+    pub fn set_media_title(&self, title: &str) {
+        self.set_property("force-media-title", title);
     }
 
     pub fn set_volume(&self, volume: i64) {
@@ -215,6 +329,10 @@ impl TsukimiMPV {
         self.set_property("sid", sid.to_string());
     }
 
+    pub fn set_secondary_sid(&self, sid: TrackSelection) {
+        self.set_property("secondary-sid", sid.to_string());
+    }
+
     pub fn seek_forward(&self, value: i64) {
         self.command("seek", &[&value.to_string()]);
     }
@@ -223,6 +341,30 @@ impl TsukimiMPV {
         self.command("seek", &[&(-value).to_string()]);
     }
 
+    /// Seeks to `position` seconds, on the exact frame whatever the seek mode.
+    pub fn seek_exact(&self, position: f64) {
+        self.command("seek", &[&position.to_string(), "absolute+exact"]);
+    }
+
+    /// Makes relative seeks land on the exact frame rather than the nearest keyframe.
+    pub fn set_exact_seek(&self, exact: bool) {
+        self.set_property("hr-seek", if exact { "yes" } else { "default" });
+    }
+
+    pub fn frame_step(&self) {
+        self.command("frame-step", &[]);
+    }
+
+    pub fn frame_back_step(&self) {
+        self.command("frame-back-step", &[]);
+    }
+
+    pub fn set_ab_loop(&self, a: Option<f64>, b: Option<f64>) {
+        let point = |point: Option<f64>| point.map_or("no".to_string(), |p| p.to_string());
+        self.set_property("ab-loop-a", point(a));
+        self.set_property("ab-loop-b", point(b));
+    }
+
     pub fn press_key(&self, key: u32, state: gtk::gdk::ModifierType) {
         let keystr = get_full_keystr(key, state);
         if let Some(keystr) = keystr {
@@ -243,10 +385,24 @@ impl TsukimiMPV {
         self.command("stop", &[]);
     }
 
+    /// Runs a command written like in mpv's input.conf, such as `add sub-delay -0.1`.
+    pub fn run_command(&self, command: &str) {
+        self.command(command, &[]);
+    }
+
     pub fn display_stats_toggle(&self) {
         self.command("script-binding", &["stats/display-stats-toggle"]);
     }
 
+    /// Saves the current frame to `path`, with the subtitles drawn in or only the video.
+    pub fn screenshot_to_file(&self, path: &str, subtitles: bool) -> bool {
+        let flags = if subtitles { "subtitles" } else { "video" };
+        let mpv = self.mpv.borrow();
+        mpv.command("screenshot-to-file", &[&quote_arg(path), flags])
+            .map_err(|e| warn!("MPV screenshot Error: {}", e))
+            .is_ok()
+    }
+
     pub fn set_property<V>(&self, property: &str, value: V)
     where
         V: SetData,
@@ -257,7 +413,7 @@ impl TsukimiMPV {
             .ok();
     }
 
-    fn get_property<V>(&self, property: &str) -> Option<V>
+    pub fn get_property<V>(&self, property: &str) -> Option<V>
     where
         V: GetData,
     {
@@ -300,6 +456,15 @@ impl TsukimiMPV {
         event_context
             .observe_property("paused-for-cache", libmpv2::Format::Flag, 4)
             .unwrap();
+        event_context
+            .observe_property("sub-delay", libmpv2::Format::Double, 5)
+            .unwrap();
+        event_context
+            .observe_property("chapter-list", libmpv2::Format::Node, 6)
+            .unwrap();
+        event_context
+            .observe_property("chapter", libmpv2::Format::Int64, 7)
+            .unwrap();
         let event_thread_alive = self.event_thread_alive.clone();
         std::thread::Builder::new()
             .name("mpv event loop".into())
@@ -348,6 +513,24 @@ impl TsukimiMPV {
                                         .send(ListenEvent::PausedForCache(pause));
                                 }
                             }
+                            "sub-delay" => {
+                                if let PropertyData::Double(delay) = change {
+                                    let _ = MPV_EVENT_CHANNEL.tx.send(ListenEvent::SubDelay(delay));
+                                }
+                            }
+                            "chapter-list" => {
+                                if let PropertyData::Node(node) = change {
+                                    let _ = MPV_EVENT_CHANNEL
+                                        .tx
+                                        .send(ListenEvent::ChapterList(node_to_chapters(node)));
+                                }
+                            }
+                            "chapter" => {
+                                if let PropertyData::Int64(chapter) = change {
+                                    let _ =
+                                        MPV_EVENT_CHANNEL.tx.send(ListenEvent::Chapter(chapter));
+                                }
+                            }
                             _ => {}
                         },
                         Event::Seek { .. } => {
@@ -365,9 +548,7 @@ impl TsukimiMPV {
                         _ => {}
                     },
                     Some(Err(e)) => {
-                        let _ = MPV_EVENT_CHANNEL
-                            .tx
-                            .send(ListenEvent::Error(e.to_user_facing()));
+                        let _ = MPV_EVENT_CHANNEL.tx.send(error_event(e));
                     }
                     None => {}
                 };
@@ -405,11 +586,30 @@ fn node_to_tracks(node: MpvNode) -> MpvTracks {
             .to_string();
 
         let type_ = range.get("type").unwrap().str().unwrap().to_string();
+        let selected = range
+            .get("selected")
+            .and_then(|v| v.bool())
+            .unwrap_or(false);
+        let forced = range.get("forced").and_then(|v| v.bool()).unwrap_or(false);
+        let hearing_impaired = range
+            .get("hearing-impaired")
+            .and_then(|v| v.bool())
+            .unwrap_or(false);
+        let ff_index = range.get("ff-index").and_then(|v| v.i64());
+        let external_filename = range
+            .get("external-filename")
+            .and_then(|v| v.str())
+            .map(|v| v.to_string());
         let track = MpvTrack {
             id,
             title,
             lang,
             type_,
+            selected,
+            forced,
+            hearing_impaired,
+            ff_index,
+            external_filename,
         };
         if track.type_ == "audio" {
             audio_tracks.push(track);
@@ -423,7 +623,52 @@ fn node_to_tracks(node: MpvNode) -> MpvTracks {
     }
 }
 
-fn get_full_keystr(key: u32, state: gtk::gdk::ModifierType) -> Option<String> {
+fn node_to_chapters(node: MpvNode) -> Vec<MpvChapter> {
+    let Some(array) = node.array() else {
+        return Vec::new();
+    };
+    array
+        .filter_map(|node| {
+            let chapter = node.map()?.collect::<HashMap<_, _>>();
+            Some(MpvChapter {
+                title: chapter
+                    .get("title")
+                    .and_then(|v| v.str())
+                    .filter(|title| !title.is_empty())
+                    .map(|title| title.to_string()),
+                time: chapter.get("time")?.f64()?,
+            })
+        })
+        .collect()
+}
+
+// Commands are parsed as one string, so arguments with spaces or quotes in them have to be
+// quoted
+fn quote_arg(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn sub_add_args(url: &str, title: &str, lang: Option<&str>) -> Vec<String> {
+    [Some(url), Some("auto"), Some(title), lang]
+        .into_iter()
+        .flatten()
+        .map(quote_arg)
+        .collect()
+}
+
+// Files mpv couldn't play end like other files, so the page can fall back to another
+// stream, the rest is only reported
+fn error_event(error: libmpv2::Error) -> ListenEvent {
+    match error {
+        libmpv2::Error::Raw(code) if END_FILE_ERRORS.contains(&code) => {
+            warn!("MPV failed to play the file: {}", code);
+            ListenEvent::Eof(END_FILE_REASON_ERROR)
+        }
+        error => ListenEvent::Error(error.to_user_facing()),
+    }
+}
+
+pub fn get_full_keystr(key: u32, state: gtk::gdk::ModifierType) -> Option<String> {
     let modstr = get_modstr(state);
     let keystr = keyval_to_keystr(key);
     if let Some(keystr) = keystr {
@@ -468,6 +713,83 @@ fn get_modstr(state: gtk::gdk::ModifierType) -> String {
     result
 }
 
+// mpv's modifier prefixes, in the order `get_modstr` writes them, and GTK's
+const MODIFIERS: [(&str, &str); 4] = [
+    ("Shift+", "<Shift>"),
+    ("Ctrl+", "<Ctrl>"),
+    ("Alt+", "<Alt>"),
+    ("Meta+", "<Super>"),
+];
+
+// Which of `MODIFIERS` an mpv key holds, and the key without them
+fn split_keystr(keystr: &str) -> ([bool; 4], &str) {
+    let mut held = [false; 4];
+    let mut key = keystr;
+    while let Some((index, rest)) = MODIFIERS
+        .iter()
+        .enumerate()
+        .find_map(|(index, (modstr, _))| {
+            key.strip_prefix(modstr)
+                .filter(|rest| !rest.is_empty())
+                .map(|rest| (index, rest))
+        })
+    {
+        held[index] = true;
+        key = rest;
+    }
+    (held, key)
+}
+
+/// `keystr` the way `get_full_keystr` writes it, without Shift for the characters it
+/// already changed, so `Shift+S` and `S` are the same key.
+pub fn normalize_keystr(keystr: &str) -> String {
+    let (mut held, key) = split_keystr(keystr.trim());
+    let mut chars = key.chars();
+    if matches!((chars.next(), chars.next()), (Some(c), None) if !c.is_whitespace()) {
+        held[0] = false;
+    }
+    let modstr = MODIFIERS
+        .iter()
+        .zip(held)
+        .filter(|(_, held)| *held)
+        .map(|((modstr, _), _)| *modstr)
+        .collect::<String>();
+    format!("{}{}", modstr, key)
+}
+
+/// The GTK accelerator of an mpv key, `<Ctrl>g` for `Ctrl+g`.
+pub fn keystr_to_accelerator(keystr: &str) -> Option<String> {
+    let (held, key) = split_keystr(keystr);
+    let mut chars = key.chars();
+    let name = match (chars.next(), chars.next()) {
+        (Some(c), None) => gtk::gdk::Key::from_unicode(c).name()?.to_string(),
+        _ => match KEYSTRING_MAP
+            .iter()
+            .rev()
+            .find(|(keystr, _)| !keystr.is_empty() && keystr.eq_ignore_ascii_case(key))
+        {
+            // Some keys are listed as the character they type
+            Some((_, name)) => match *name {
+                "\x08" => "BackSpace",
+                "\x1b" => "Escape",
+                "\x7f" => "Delete",
+                "\r" => "Return",
+                " " => "space",
+                name => name,
+            }
+            .to_string(),
+            None => key.to_string(),
+        },
+    };
+    let modstr = MODIFIERS
+        .iter()
+        .zip(held)
+        .filter(|(_, held)| *held)
+        .map(|((_, accel), _)| *accel)
+        .collect::<String>();
+    Some(format!("{}{}", modstr, name))
+}
+
 use gtk::glib::translate::FromGlib;
 
 use crate::{client::error::UserFacingError, ui::models::SETTINGS};
@@ -534,3 +856,85 @@ fn keyval_to_keystr(keyval: u32) -> Option<String> {
         .map(|(keystr, _)| keystr.to_string())
         .or(Some(key_name))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_add_quotes_args() {
+        let args = sub_add_args(
+            "/home/me/My Subtitles/Episode \"1\".srt",
+            "English - SRT - External",
+            Some("eng"),
+        );
+        assert_eq!(
+            format!("sub-add {}", args.join(" ")),
+            r#"sub-add "/home/me/My Subtitles/Episode \"1\".srt" "auto" "English - SRT - External" "eng""#
+        );
+    }
+
+    #[test]
+    fn sub_add_without_lang() {
+        let args = sub_add_args(r"C:\Subs\a b.ass", "a b.ass", None);
+        assert_eq!(
+            args,
+            [r#""C:\\Subs\\a b.ass""#, r#""auto""#, r#""a b.ass""#]
+        );
+    }
+
+    #[test]
+    fn end_file_errors_end_the_file() {
+        for code in END_FILE_ERRORS {
+            assert!(matches!(
+                error_event(libmpv2::Error::Raw(*code)),
+                ListenEvent::Eof(END_FILE_REASON_ERROR)
+            ));
+        }
+    }
+
+    #[test]
+    fn other_errors_are_reported() {
+        for error in [
+            libmpv2::Error::Raw(libmpv2::mpv_error::PropertyUnavailable),
+            libmpv2::Error::Raw(libmpv2::mpv_error::Command),
+            libmpv2::Error::Raw(libmpv2::mpv_error::InvalidParameter),
+            libmpv2::Error::Null,
+        ] {
+            assert!(matches!(error_event(error), ListenEvent::Error(_)));
+        }
+    }
+
+    #[test]
+    fn normalize_keystr_folds_shift() {
+        assert_eq!(normalize_keystr("Shift+S"), "S");
+        assert_eq!(normalize_keystr("Shift+Ctrl+g"), "Ctrl+g");
+        assert_eq!(normalize_keystr("Shift+Tab"), "Shift+Tab");
+        assert_eq!(normalize_keystr("Shift+SPACE"), "Shift+SPACE");
+    }
+
+    #[test]
+    fn normalize_keystr_orders_modifiers() {
+        assert_eq!(normalize_keystr("Alt+Ctrl+x"), "Ctrl+Alt+x");
+        assert_eq!(normalize_keystr("Meta+Shift+F8"), "Shift+Meta+F8");
+        assert_eq!(normalize_keystr(" Ctrl++ "), "Ctrl++");
+    }
+
+    #[test]
+    fn accelerators() {
+        assert_eq!(keystr_to_accelerator("Ctrl+g").as_deref(), Some("<Ctrl>g"));
+        assert_eq!(keystr_to_accelerator("S").as_deref(), Some("S"));
+        assert_eq!(keystr_to_accelerator("[").as_deref(), Some("bracketleft"));
+        assert_eq!(
+            keystr_to_accelerator("Shift+Tab").as_deref(),
+            Some("<Shift>Tab")
+        );
+        assert_eq!(keystr_to_accelerator("BS").as_deref(), Some("BackSpace"));
+        assert_eq!(keystr_to_accelerator("ENTER").as_deref(), Some("Return"));
+        assert_eq!(keystr_to_accelerator("F8").as_deref(), Some("F8"));
+        assert_eq!(
+            keystr_to_accelerator("Ctrl+Alt+DEL").as_deref(),
+            Some("<Ctrl><Alt>Delete")
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use tracing::warn;
+
+use crate::client::client::EMBY_CLIENT;
+use crate::gstl::player::imp::ListRepeatMode;
+use crate::ui::provider::tu_item::TuItem;
+use crate::utils::spawn_tokio;
+
+/// The video that plays after the current one.
+#[derive(Clone)]
+pub struct UpNext {
+    pub item: TuItem,
+    /// Playlist to continue with, when `item` isn't in the current one
+    pub playlist: Option<Vec<TuItem>>,
+}
+
+/// Finds what plays after `current`: the next video of `playlist`, its first one again
+/// when repeating the whole playlist, then the next episode of the series, which may be in
+/// another season, then the series' next up.
+pub async fn resolve_up_next(
+    current: &TuItem,
+    playlist: &[TuItem],
+    repeat_mode: ListRepeatMode,
+) -> Option<UpNext> {
+    if let Some(index) = playlist.iter().position(|item| item.id() == current.id()) {
+        let next = match repeat_mode {
+            ListRepeatMode::Repeat => playlist.get((index + 1) % playlist.len()),
+            _ => playlist.get(index + 1),
+        };
+        if let Some(item) = next {
+            return Some(UpNext {
+                item: item.clone(),
+                playlist: None,
+            });
+        }
+    }
+
+    let series_id = current.series_id()?;
+    // A queue mixing several shows ends with its last video
+    if playlist
+        .iter()
+        .any(|item| item.series_id().as_ref() != Some(&series_id))
+    {
+        return None;
+    }
+
+    let id = series_id.clone();
+    match spawn_tokio(async move { EMBY_CLIENT.get_series_episodes(&id).await }).await {
+        Ok(list) => {
+            let episodes = list
+                .items
+                .iter()
+                .map(|episode| TuItem::from_simple(episode, None))
+                .collect::<Vec<_>>();
+            // The last episode of the series has nothing after it
+            if let Some(index) = episodes.iter().position(|item| item.id() == current.id()) {
+                let item = episodes.get(index + 1).cloned()?;
+                return Some(UpNext {
+                    item,
+                    playlist: Some(episodes),
+                });
+            }
+        }
+        Err(e) => warn!("Failed to load episodes of {}: {}", series_id, e),
+    }
+
+    let next_up =
+        match spawn_tokio(async move { EMBY_CLIENT.get_shows_next_up(&series_id).await }).await {
+            Ok(next_up) => next_up,
+            Err(e) => {
+                warn!("Failed to load next up: {}", e);
+                return None;
+            }
+        };
+    let item = next_up
+        .items
+        .first()
+        .map(|item| TuItem::from_simple(item, None))
+        .filter(|item| item.id() != current.id())?;
+    Some(UpNext {
+        playlist: Some(vec![item.clone()]),
+        item,
+    })
+}
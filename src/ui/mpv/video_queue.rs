@@ -0,0 +1,93 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use anyhow::Result;
+
+use crate::client::client::EMBY_CLIENT;
+use crate::ui::provider::tu_item::TuItem;
+use crate::utils::spawn_tokio;
+
+// Videos the server returns per request
+const QUEUE_PAGE_SIZE: usize = 100;
+const MAX_QUEUE_LENGTH: usize = 2000;
+const SERIES_ORDER: &str = "SeriesSortName,ParentIndexNumber,IndexNumber,SortName";
+
+/// Where a Play All queue takes its videos from.
+#[derive(Debug, Clone)]
+pub enum QueueSource {
+    /// A box set or playlist, played in its own order
+    Collection(String),
+    /// A library folder
+    Folder(String),
+    Genre {
+        id: String,
+        parent_id: Option<String>,
+    },
+    Tag {
+        id: String,
+        parent_id: Option<String>,
+    },
+}
+
+impl QueueSource {
+    fn parent_id(&self) -> Option<&str> {
+        match self {
+            QueueSource::Collection(id) | QueueSource::Folder(id) => Some(id),
+            QueueSource::Genre { parent_id, .. } | QueueSource::Tag { parent_id, .. } => {
+                parent_id.as_deref()
+            }
+        }
+    }
+
+    fn filter(&self) -> Option<(&'static str, &str)> {
+        match self {
+            QueueSource::Genre { id, .. } => Some(("GenreIds", id)),
+            QueueSource::Tag { id, .. } => Some(("TagIds", id)),
+            _ => None,
+        }
+    }
+
+    fn sort_by(&self) -> Option<&'static str> {
+        match self {
+            QueueSource::Collection(_) => None,
+            _ => Some(SERIES_ORDER),
+        }
+    }
+}
+
+/// Pages through the videos of `source`, in random order if `shuffle` is set.
+pub async fn fetch_queue(source: QueueSource, shuffle: bool) -> Result<Vec<TuItem>> {
+    let items = spawn_tokio(async move {
+        let mut items = Vec::new();
+        loop {
+            let page = EMBY_CLIENT
+                .get_video_queue(
+                    source.parent_id(),
+                    source.filter(),
+                    source.sort_by(),
+                    items.len() as u32,
+                )
+                .await?;
+            let count = page.items.len();
+            items.extend(page.items);
+            if count < QUEUE_PAGE_SIZE
+                || items.len() >= page.total_record_count as usize
+                || items.len() >= MAX_QUEUE_LENGTH
+            {
+                break;
+            }
+        }
+        Ok::<_, anyhow::Error>(items)
+    })
+    .await?;
+
+    let mut queue = items
+        .iter()
+        .map(|item| TuItem::from_simple(item, None))
+        .collect::<Vec<_>>();
+    if shuffle {
+        let state = RandomState::new();
+        queue.sort_by_cached_key(|item| state.hash_one(item.id()));
+    }
+    Ok(queue)
+}
@@ -1,10 +1,16 @@
 use gtk::{glib, prelude::*, subclass::prelude::*};
 
+use super::ab_loop::AbLoop;
+use super::trickplay::Trickplay;
+
 mod imp {
-    use gtk::{glib, prelude::*, subclass::prelude::*};
-    use std::cell::RefCell;
+    use gtk::{gdk, glib, prelude::*, subclass::prelude::*};
+    use std::cell::{Cell, OnceCell, RefCell};
 
+    use crate::ui::mpv::ab_loop::AbLoop;
     use crate::ui::mpv::mpvglarea::MPVGLArea;
+    use crate::ui::mpv::trickplay::Trickplay;
+    use crate::ui::widgets::song_widget::format_duration;
 
     #[derive(Default, glib::Properties)]
     #[properties(wrapper_type = super::VideoScale)]
@@ -12,6 +18,12 @@ mod imp {
         pub timeout: RefCell<Option<glib::source::SourceId>>,
         #[property(get, set = Self::set_player, explicit_notify, nullable)]
         pub player: glib::WeakRef<MPVGLArea>,
+        pub trickplay: RefCell<Option<Trickplay>>,
+        pub chapter_starts: RefCell<Vec<f64>>,
+        pub ab_loop: Cell<AbLoop>,
+        pub preview: OnceCell<gtk::Popover>,
+        pub preview_picture: gtk::Picture,
+        pub preview_label: gtk::Label,
     }
 
     #[glib::object_subclass]
@@ -56,6 +68,14 @@ mod imp {
                     imp.on_click_released();
                 }
             ));
+
+            self.setup_preview();
+        }
+
+        fn dispose(&self) {
+            if let Some(preview) = self.preview.get() {
+                preview.unparent();
+            }
         }
     }
     impl WidgetImpl for VideoScale {}
@@ -84,6 +104,82 @@ mod imp {
         fn on_seek_finished(&self, value: f64) {
             self.player.upgrade().unwrap().set_position(value);
         }
+
+        fn setup_preview(&self) {
+            let obj = self.obj();
+
+            self.preview_picture.set_size_request(160, 90);
+            self.preview_picture
+                .set_content_fit(gtk::ContentFit::Contain);
+            self.preview_label.add_css_class("numeric");
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(6)
+                .build();
+            content.append(&self.preview_picture);
+            content.append(&self.preview_label);
+
+            let preview = gtk::Popover::builder()
+                .child(&content)
+                .autohide(false)
+                .can_focus(false)
+                .has_arrow(false)
+                .position(gtk::PositionType::Top)
+                .build();
+            preview.set_parent(&*obj);
+            let _ = self.preview.set(preview);
+
+            // Motion keeps coming in while the slider is dragged
+            let motion = gtk::EventControllerMotion::new();
+            motion.connect_motion(glib::clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, x, _| {
+                    imp.show_preview(x);
+                }
+            ));
+            motion.connect_leave(glib::clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| {
+                    if let Some(preview) = imp.preview.get() {
+                        preview.popdown();
+                    }
+                }
+            ));
+            obj.add_controller(motion);
+        }
+
+        /// Shows the time, and the thumbnail if there are previews, at `x` on the bar.
+        fn show_preview(&self, x: f64) {
+            let obj = self.obj();
+            let Some(preview) = self.preview.get() else {
+                return;
+            };
+            let rect = obj.range_rect();
+            let adjustment = obj.adjustment();
+            if rect.width() <= 0 || adjustment.upper() <= adjustment.lower() {
+                return;
+            }
+
+            let fraction = ((x - rect.x() as f64) / rect.width() as f64).clamp(0.0, 1.0);
+            let position =
+                adjustment.lower() + fraction * (adjustment.upper() - adjustment.lower());
+            self.preview_label
+                .set_text(&format_duration(position as i64));
+            let thumbnail = self
+                .trickplay
+                .borrow()
+                .as_ref()
+                .and_then(|trickplay| trickplay.thumbnail(position));
+            self.preview_picture.set_visible(thumbnail.is_some());
+            self.preview_picture.set_paintable(thumbnail.as_ref());
+
+            preview.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, 0, 1, 1)));
+            if !preview.is_visible() {
+                preview.popup();
+            }
+        }
     }
 }
 
@@ -103,6 +199,11 @@ impl VideoScale {
         glib::Object::builder().build()
     }
 
+    /// Sets the thumbnails shown while hovering the bar, `None` to only show the time.
+    pub fn set_trickplay(&self, trickplay: Option<Trickplay>) {
+        self.imp().trickplay.replace(trickplay);
+    }
+
     pub fn update_position_callback(&self) -> glib::ControlFlow {
         let position = &self.player().unwrap().position();
         if *position > 0.0 {
@@ -132,6 +233,38 @@ impl VideoScale {
         }
     }
 
+    /// Draws a tick where each chapter starts, in seconds.
+    pub fn set_chapter_marks(&self, starts: &[f64]) {
+        self.imp().chapter_starts.replace(starts.to_vec());
+        self.update_marks();
+    }
+
+    /// Marks the loop points above the bar.
+    pub fn set_ab_loop(&self, ab_loop: AbLoop) {
+        self.imp().ab_loop.set(ab_loop);
+        self.update_marks();
+    }
+
+    fn update_marks(&self) {
+        let imp = self.imp();
+        self.clear_marks();
+        for start in imp
+            .chapter_starts
+            .borrow()
+            .iter()
+            .filter(|start| **start > 0.0)
+        {
+            self.add_mark(*start, gtk::PositionType::Bottom, None);
+        }
+        let ab_loop = imp.ab_loop.get();
+        if let Some(a) = ab_loop.a {
+            self.add_mark(a, gtk::PositionType::Top, Some("A"));
+        }
+        if let Some(b) = ab_loop.b {
+            self.add_mark(b, gtk::PositionType::Top, Some("B"));
+        }
+    }
+
     pub fn on_smooth_scale_value_changed(&self) {
         let value = self.value();
         let position = value / 60.0;
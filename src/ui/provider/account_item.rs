@@ -1,7 +1,7 @@
 use gtk::glib;
 use gtk::glib::prelude::*;
 use gtk::glib::subclass::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use crate::config::Account;
 
@@ -27,6 +27,10 @@ pub mod imp {
         user_id: RefCell<String>,
         #[property(get, set)]
         access_token: RefCell<String>,
+        #[property(get, set)]
+        max_bitrate: Cell<u64>,
+        #[property(get, set)]
+        max_bitrate_metered: Cell<u64>,
     }
 
     #[glib::derived_properties]
@@ -54,6 +58,8 @@ impl AccountItem {
         item.set_port(account.port);
         item.set_user_id(account.user_id);
         item.set_access_token(account.access_token);
+        item.set_max_bitrate(account.max_bitrate);
+        item.set_max_bitrate_metered(account.max_bitrate_metered);
         item
     }
 
@@ -66,6 +72,8 @@ impl AccountItem {
             port: self.port(),
             user_id: self.user_id(),
             access_token: self.access_token(),
+            max_bitrate: self.max_bitrate(),
+            max_bitrate_metered: self.max_bitrate_metered(),
         }
     }
 }
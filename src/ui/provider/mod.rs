@@ -1,6 +1,7 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use once_cell::sync::Lazy;
+use tokio::sync::watch;
 
 pub mod account_item;
 pub mod actions;
@@ -16,3 +17,28 @@ pub static IS_ADMIN: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 pub fn set_admin(value: bool) {
     IS_ADMIN.store(value, Ordering::SeqCst);
 }
+
+pub static IS_OFFLINE: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::Sender::new(false));
+
+pub fn set_offline(value: bool) {
+    IS_OFFLINE.send_if_modified(|offline| std::mem::replace(offline, value) != value);
+}
+
+pub fn is_offline() -> bool {
+    *IS_OFFLINE.borrow()
+}
+
+/// Notified whenever offline mode is turned on or off.
+pub fn subscribe_offline() -> watch::Receiver<bool> {
+    IS_OFFLINE.subscribe()
+}
+
+pub static IS_METERED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
+
+pub fn set_metered(value: bool) {
+    IS_METERED.store(value, Ordering::SeqCst);
+}
+
+pub fn is_metered() -> bool {
+    IS_METERED.load(Ordering::SeqCst)
+}
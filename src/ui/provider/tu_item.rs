@@ -1,4 +1,4 @@
-use crate::client::structs::SimpleListItem;
+use crate::client::structs::{PlayMethod, SimpleListItem};
 use crate::ui::widgets::single_grid::SingleGrid;
 use gettextrs::gettext;
 use glib::DateTime;
@@ -9,6 +9,7 @@ use std::cell::RefCell;
 
 use crate::client::client::EMBY_CLIENT;
 use crate::client::error::UserFacingError;
+use crate::ui::mpv::video_queue::QueueSource;
 use crate::ui::widgets::window::Window;
 use crate::utils::spawn_tokio;
 use crate::{
@@ -277,7 +278,7 @@ impl TuItem {
                 let page = ActorPage::new(&self.id());
                 push_page_with_tag(window, page, self.name());
             }
-            "BoxSet" => {
+            "BoxSet" | "Playlist" => {
                 let page = BoxSetPage::new(&self.id());
                 push_page_with_tag(window, page, self.name());
             }
@@ -317,6 +318,18 @@ impl TuItem {
                             .await
                     }
                 });
+                let source = if self.item_type() == "Genre" {
+                    QueueSource::Genre {
+                        id: self.id(),
+                        parent_id: parentid.clone(),
+                    }
+                } else {
+                    QueueSource::Tag {
+                        id: self.id(),
+                        parent_id: parentid.clone(),
+                    }
+                };
+                page.set_queue_source(source);
                 page.emit_by_name::<()>("sort-changed", &[]);
                 push_page_with_tag(window, page, self.name());
             }
@@ -338,7 +351,16 @@ impl TuItem {
                             toast!(window, gettext("No transcoding url found"));
                             return;
                         };
-                        window.play_media(url.to_string(), None, item, Vec::new(), None, None, 0.0)
+                        window.play_media(
+                            url.to_string(),
+                            PlayMethod::Transcode,
+                            None,
+                            item,
+                            Vec::new(),
+                            None,
+                            None,
+                            0.0,
+                        )
                     }
                     Err(e) => {
                         toast!(window, e.to_user_facing());
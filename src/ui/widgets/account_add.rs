@@ -123,6 +123,8 @@ impl AccountWindow {
             port: port.to_string(),
             user_id: res.user.id,
             access_token: res.access_token,
+            max_bitrate: 0,
+            max_bitrate_metered: 0,
         };
 
         match save_cfg(account).await {
@@ -3,7 +3,9 @@
 use crate::{
     client::client::EMBY_CLIENT,
     toast,
-    ui::models::{emby_cache_path, SETTINGS},
+    ui::models::{emby_cache_path, texture_cache::TEXTURE_CACHE, SETTINGS},
+    ui::mpv::language_rules::LanguageRules,
+    ui::mpv::screenshot::screenshot_dir,
     utils::spawn_tokio,
 };
 use adw::prelude::*;
@@ -11,6 +13,8 @@ use adw::subclass::prelude::*;
 use gettextrs::gettext;
 use gtk::{gdk::RGBA, gio, glib, template_callbacks, CompositeTemplate};
 
+use super::keybindings_dialog::KeybindingsDialog;
+
 mod imp {
     use super::*;
     use glib::subclass::InitializingObject;
@@ -57,8 +61,23 @@ mod imp {
         #[template_child]
         pub seek_backward_spinrow: TemplateChild<adw::SpinRow>,
         #[template_child]
+        pub exact_seek_switchrow: TemplateChild<adw::SwitchRow>,
+        #[template_child]
         pub config_switchrow: TemplateChild<adw::SwitchRow>,
 
+        #[template_child]
+        pub played_threshold_spinrow: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub resume_min_position_spinrow: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub resume_prompt_switchrow: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub up_next_spinrow: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub screenshot_dir_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub screenshot_copy_switchrow: TemplateChild<adw::SwitchRow>,
+
         #[template_child]
         pub buffer_switchrow: TemplateChild<adw::SwitchRow>,
 
@@ -77,9 +96,17 @@ mod imp {
         pub mpv_sub_size_spinrow: TemplateChild<adw::SpinRow>,
 
         #[template_child]
-        pub preferred_audio_language_comborow: TemplateChild<adw::ComboRow>,
+        pub audio_lang_priority_entryrow: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub subtitle_lang_priority_entryrow: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub forced_subs_switchrow: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub allow_sdh_switchrow: TemplateChild<adw::SwitchRow>,
         #[template_child]
-        pub preferred_subtitle_language_comborow: TemplateChild<adw::ComboRow>,
+        pub skip_understood_switchrow: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub lang_preview_row: TemplateChild<adw::ActionRow>,
 
         #[template_child]
         pub video_subpage: TemplateChild<adw::NavigationPage>,
@@ -124,6 +151,28 @@ mod imp {
                     set.clear_font();
                 },
             );
+            klass.install_action_async(
+                "setting.screenshot-dir",
+                None,
+                |set, _action, _parameter| async move {
+                    set.set_screenshot_dir().await;
+                },
+            );
+            klass.install_action(
+                "setting.keybindings",
+                None,
+                move |set, _action, _parameter| {
+                    set.edit_keybindings();
+                },
+            );
+            klass.install_action(
+                "setting.screenshot-dir-clear",
+                None,
+                move |set, _action, _parameter| {
+                    SETTINGS.set_mpv_screenshot_dir("").unwrap();
+                    set.update_screenshot_dir();
+                },
+            );
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -285,6 +334,7 @@ impl AccountSettings {
         if path.exists() {
             std::fs::remove_dir_all(path).unwrap();
         }
+        TEXTURE_CACHE.clear();
         toast!(self, gettext("Cache Cleared"))
     }
 
@@ -318,6 +368,45 @@ impl AccountSettings {
         };
     }
 
+    pub async fn set_screenshot_dir(&self) {
+        let filedialog = gtk::FileDialog::builder()
+            .modal(true)
+            .title(gettext("Select a folder"))
+            .initial_folder(&gio::File::for_path(screenshot_dir()))
+            .build();
+        match filedialog.select_folder_future(Some(&self.window())).await {
+            Ok(folder) => {
+                let Some(path) = folder.path() else {
+                    toast!(self, gettext("This folder can't be used"));
+                    return;
+                };
+                SETTINGS
+                    .set_mpv_screenshot_dir(&path.display().to_string())
+                    .unwrap();
+                self.update_screenshot_dir();
+            }
+            Err(_) => toast!(self, gettext("No folder selected")),
+        };
+    }
+
+    pub fn edit_keybindings(&self) {
+        let dialog = KeybindingsDialog::new();
+        dialog.connect_changed(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                obj.window().reload_keybindings();
+            }
+        ));
+        dialog.present(Some(self));
+    }
+
+    pub fn update_screenshot_dir(&self) {
+        self.imp()
+            .screenshot_dir_row
+            .set_subtitle(&screenshot_dir().display().to_string());
+    }
+
     pub fn set_picopactiy(&self) {
         let imp = self.imp();
         imp.backgroundspinrow
@@ -432,7 +521,20 @@ impl AccountSettings {
             .set_value(SETTINGS.mpv_seek_backward_step().into());
         imp.seek_forward_spinrow
             .set_value(SETTINGS.mpv_seek_forward_step().into());
+        imp.exact_seek_switchrow
+            .set_active(SETTINGS.mpv_exact_seek());
         imp.config_switchrow.set_active(SETTINGS.mpv_config());
+        imp.played_threshold_spinrow
+            .set_value(SETTINGS.mpv_played_threshold().into());
+        imp.resume_min_position_spinrow
+            .set_value(SETTINGS.mpv_resume_min_position().into());
+        imp.resume_prompt_switchrow
+            .set_active(SETTINGS.mpv_resume_prompt());
+        imp.up_next_spinrow
+            .set_value(SETTINGS.mpv_up_next_seconds().into());
+        imp.screenshot_copy_switchrow
+            .set_active(SETTINGS.mpv_screenshot_copy());
+        self.update_screenshot_dir();
         imp.buffer_switchrow
             .set_active(SETTINGS.mpv_show_buffer_speed());
         imp.stereo_switchrow.set_active(SETTINGS.mpv_force_stereo());
@@ -446,10 +548,17 @@ impl AccountSettings {
             .set_value(SETTINGS.mpv_cache_size().into());
         imp.mpv_sub_size_spinrow
             .set_value(SETTINGS.mpv_subtitle_size().into());
-        imp.preferred_audio_language_comborow
-            .set_selected(SETTINGS.mpv_audio_preferred_lang() as u32);
-        imp.preferred_subtitle_language_comborow
-            .set_selected(SETTINGS.mpv_subtitle_preferred_lang() as u32);
+        imp.audio_lang_priority_entryrow
+            .set_text(&SETTINGS.mpv_audio_lang_priority().join(", "));
+        imp.subtitle_lang_priority_entryrow
+            .set_text(&SETTINGS.mpv_subtitle_lang_priority().join(", "));
+        imp.forced_subs_switchrow
+            .set_active(SETTINGS.mpv_subtitle_forced_for_ui_lang());
+        imp.allow_sdh_switchrow
+            .set_active(SETTINGS.mpv_subtitle_allow_sdh());
+        imp.skip_understood_switchrow
+            .set_active(SETTINGS.mpv_subtitle_skip_understood_audio());
+        self.update_lang_preview();
         let action_group = gio::SimpleActionGroup::new();
 
         let action_video_end = gio::ActionEntry::builder("video-end")
@@ -528,6 +637,44 @@ impl AccountSettings {
             .unwrap();
     }
 
+    #[template_callback]
+    pub fn on_exact_seek_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
+        SETTINGS.set_mpv_exact_seek(control.is_active()).unwrap();
+    }
+
+    #[template_callback]
+    pub fn on_played_threshold_spinrow(&self, _param: glib::ParamSpec, spin: adw::SpinRow) {
+        SETTINGS
+            .set_mpv_played_threshold(spin.value() as i32)
+            .unwrap();
+    }
+
+    #[template_callback]
+    pub fn on_resume_min_position_spinrow(&self, _param: glib::ParamSpec, spin: adw::SpinRow) {
+        SETTINGS
+            .set_mpv_resume_min_position(spin.value() as i32)
+            .unwrap();
+    }
+
+    #[template_callback]
+    pub fn on_resume_prompt_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
+        SETTINGS.set_mpv_resume_prompt(control.is_active()).unwrap();
+    }
+
+    #[template_callback]
+    pub fn on_screenshot_copy_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
+        SETTINGS
+            .set_mpv_screenshot_copy(control.is_active())
+            .unwrap();
+    }
+
+    #[template_callback]
+    pub fn on_up_next_spinrow(&self, _param: glib::ParamSpec, spin: adw::SpinRow) {
+        SETTINGS
+            .set_mpv_up_next_seconds(spin.value() as i32)
+            .unwrap();
+    }
+
     #[template_callback]
     pub fn on_cachesize_spinrow(&self, _param: glib::ParamSpec, spin: adw::SpinRow) {
         SETTINGS.set_mpv_cache_size(spin.value() as i32).unwrap();
@@ -544,17 +691,56 @@ impl AccountSettings {
     }
 
     #[template_callback]
-    pub fn on_audio_language_comborow(&self, _param: glib::ParamSpec, combo: adw::ComboRow) {
+    pub fn on_audio_lang_priority_apply(&self, entry: adw::EntryRow) {
+        let langs = entry.text();
+        SETTINGS
+            .set_mpv_audio_lang_priority(&parse_lang_list(&langs))
+            .unwrap();
+        self.update_lang_preview();
+    }
+
+    #[template_callback]
+    pub fn on_subtitle_lang_priority_apply(&self, entry: adw::EntryRow) {
+        let langs = entry.text();
+        SETTINGS
+            .set_mpv_subtitle_lang_priority(&parse_lang_list(&langs))
+            .unwrap();
+        self.update_lang_preview();
+    }
+
+    #[template_callback]
+    pub fn on_forced_subs_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
         SETTINGS
-            .set_mpv_audio_preferred_lang(combo.selected() as i32)
+            .set_mpv_subtitle_forced_for_ui_lang(control.is_active())
             .unwrap();
+        self.update_lang_preview();
     }
 
     #[template_callback]
-    pub fn on_subtitle_language_comborow(&self, _param: glib::ParamSpec, combo: adw::ComboRow) {
+    pub fn on_allow_sdh_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
         SETTINGS
-            .set_mpv_subtitle_preferred_lang(combo.selected() as i32)
+            .set_mpv_subtitle_allow_sdh(control.is_active())
             .unwrap();
+        self.update_lang_preview();
+    }
+
+    #[template_callback]
+    pub fn on_skip_understood_switchrow(&self, _param: glib::ParamSpec, control: adw::SwitchRow) {
+        SETTINGS
+            .set_mpv_subtitle_skip_understood_audio(control.is_active())
+            .unwrap();
+        self.update_lang_preview();
+    }
+
+    fn update_lang_preview(&self) {
+        let (audio, subtitle) = LanguageRules::from_settings().preview();
+        self.imp().lang_preview_row.set_subtitle(&format!(
+            "{}: {} · {}: {}",
+            gettext("Audio"),
+            audio,
+            gettext("Subtitles"),
+            subtitle
+        ));
     }
 
     #[template_callback]
@@ -599,3 +785,11 @@ impl AccountSettings {
         self.push_subpage(&subpage);
     }
 }
+
+fn parse_lang_list(langs: &str) -> Vec<&str> {
+    langs
+        .split(',')
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty())
+        .collect()
+}
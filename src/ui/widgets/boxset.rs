@@ -10,6 +10,7 @@ use gtk::{gio, glib};
 use crate::client::client::EMBY_CLIENT;
 use crate::client::error::UserFacingError;
 use crate::client::structs::*;
+use crate::ui::mpv::video_queue::QueueSource;
 use crate::utils::{fetch_with_cache, get_image_with_cache, spawn, CachePolicy};
 use crate::{fraction, fraction_reset, toast};
 
@@ -209,6 +210,23 @@ impl BoxSetPage {
         ));
     }
 
+    #[template_callback]
+    async fn on_play_all_clicked(&self) {
+        self.play_queue(false).await;
+    }
+
+    #[template_callback]
+    async fn on_shuffle_clicked(&self) {
+        self.play_queue(true).await;
+    }
+
+    async fn play_queue(&self, shuffle: bool) {
+        let window = self.root().and_downcast::<super::window::Window>().unwrap();
+        window
+            .play_queue(QueueSource::Collection(self.id()), shuffle)
+            .await;
+    }
+
     pub fn setlinksscrolled(&self, links: Vec<Urls>) {
         let imp = self.imp();
 
@@ -3,7 +3,7 @@ use crate::client::error::UserFacingError;
 use crate::client::structs::*;
 use crate::ui::models::SETTINGS;
 use crate::ui::provider::tu_item::TuItem;
-use crate::utils::{fetch_with_cache, spawn, CachePolicy};
+use crate::utils::{fetch_with_cache, fetch_with_cache_and_refresh, spawn, CachePolicy};
 use crate::{fraction, fraction_reset, toast};
 use chrono::{Datelike, Local};
 use gettextrs::gettext;
@@ -48,6 +48,8 @@ mod imp {
         pub carouseloverlay: TemplateChild<gtk::Overlay>,
         pub selection: gtk::SingleSelection,
         pub timeout: RefCell<Option<glib::source::SourceId>>,
+        /// Latest rows in `libsbox`, by library id
+        pub lib_rows: RefCell<Vec<(String, HortuScrolled)>>,
     }
 
     // The central trait for subclassing a GObject
@@ -177,9 +179,19 @@ impl HomePage {
     pub async fn setup_library(&self) {
         let hortu = self.imp().libhortu.get();
 
-        let results = match fetch_with_cache("library", CachePolicy::ReadCacheAndRefresh, async {
-            EMBY_CLIENT.get_library().await
-        })
+        let results = match fetch_with_cache_and_refresh(
+            "library",
+            CachePolicy::ReadCacheAndRefresh,
+            async { EMBY_CLIENT.get_library().await },
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |results: List| {
+                    obj.imp().libhortu.update_items(&results.items);
+                    obj.update_libsview(results.items);
+                }
+            ),
+        )
         .await
         {
             Ok(history) => history,
@@ -204,56 +216,140 @@ impl HomePage {
             libsbox.remove(&libsbox.last_child().unwrap());
         }
 
-        for view in items {
-            let ac_view = view.clone();
+        let mut rows = Vec::new();
+        let mut handles = Vec::new();
 
-            let Some(collection_type) = view.collection_type else {
+        for view in items {
+            let Some(collection_type) = view.collection_type.clone() else {
                 continue;
             };
 
-            let results = match fetch_with_cache(
-                &format!("library_{}", view.id),
-                CachePolicy::ReadCacheAndRefresh,
+            // Append every row up front so they keep the library order however fast each one loads
+            let hortu = self.lib_row(&view);
+            libsbox.append(&hortu);
+            rows.push((view.id.clone(), hortu.clone()));
+
+            handles.push(glib::spawn_future_local(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                hortu,
                 async move {
-                    if collection_type == "livetv" {
-                        EMBY_CLIENT.get_channels().await.map(|x| x.items)
-                    } else {
-                        EMBY_CLIENT.get_latest(&view.id).await
-                    }
-                },
-            )
-            .await
-            {
-                Ok(history) => history,
-                Err(e) => {
-                    toast!(self, e.to_user_facing());
-                    return;
+                    obj.setup_libview(&hortu, view.id, collection_type).await;
                 }
-            };
+            )));
+        }
 
-            let hortu = HortuScrolled::new(false);
+        self.imp().lib_rows.replace(rows);
 
-            hortu.set_moreview(true);
+        for handle in handles {
+            let _ = handle.await;
+        }
+    }
 
-            hortu.set_title(&format!("{} {}", gettext("Latest"), view.name));
+    /// Brings the rows in line with a refreshed library list. Rows of libraries that are
+    /// still there stay as they are, their items refresh on their own.
+    fn update_libsview(&self, items: Vec<SimpleListItem>) {
+        let imp = self.imp();
+        let libsbox = &imp.libsbox;
+        let mut old_rows = imp.lib_rows.take();
+        let mut rows = Vec::new();
+        let mut previous: Option<HortuScrolled> = None;
 
-            hortu.set_items(&results);
+        for view in items {
+            let Some(collection_type) = view.collection_type.clone() else {
+                continue;
+            };
 
-            hortu.connect_morebutton(glib::clone!(
-                #[weak(rename_to = obj)]
-                self,
-                move |_| {
-                    let list_item = TuItem::default();
-                    list_item.set_id(ac_view.id.clone());
-                    list_item.set_name(ac_view.name.clone());
-                    list_item.set_item_type(ac_view.latest_type.clone());
-                    list_item.set_collection_type(ac_view.collection_type.clone());
-                    list_item.activate(&obj, None);
+            let hortu = match old_rows.iter().position(|(id, _)| *id == view.id) {
+                Some(index) => {
+                    let (_, hortu) = old_rows.remove(index);
+                    hortu.set_title(&format!("{} {}", gettext("Latest"), view.name));
+                    hortu
+                }
+                None => {
+                    let hortu = self.lib_row(&view);
+                    libsbox.append(&hortu);
+                    let id = view.id.clone();
+                    spawn(glib::clone!(
+                        #[weak(rename_to = obj)]
+                        self,
+                        #[weak]
+                        hortu,
+                        async move {
+                            obj.setup_libview(&hortu, id, collection_type).await;
+                        }
+                    ));
+                    hortu
                 }
-            ));
+            };
+            libsbox.reorder_child_after(&hortu, previous.as_ref());
+            previous = Some(hortu.clone());
+            rows.push((view.id, hortu));
+        }
 
-            libsbox.append(&hortu);
+        for (_, hortu) in old_rows {
+            libsbox.remove(&hortu);
         }
+        imp.lib_rows.replace(rows);
+    }
+
+    // An empty Latest row of `view`
+    fn lib_row(&self, view: &SimpleListItem) -> HortuScrolled {
+        let hortu = HortuScrolled::new(false);
+
+        hortu.set_moreview(true);
+
+        hortu.set_title(&format!("{} {}", gettext("Latest"), view.name));
+
+        let ac_view = view.clone();
+        hortu.connect_morebutton(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                let list_item = TuItem::default();
+                list_item.set_id(ac_view.id.clone());
+                list_item.set_name(ac_view.name.clone());
+                list_item.set_item_type(ac_view.latest_type.clone());
+                list_item.set_collection_type(ac_view.collection_type.clone());
+                list_item.activate(&obj, None);
+            }
+        ));
+
+        hortu.set_placeholder();
+        hortu
+    }
+
+    async fn setup_libview(&self, hortu: &HortuScrolled, id: String, collection_type: String) {
+        let results = match fetch_with_cache_and_refresh(
+            &format!("library_{}", id),
+            CachePolicy::ReadCacheAndRefresh,
+            async move {
+                if collection_type == "livetv" {
+                    EMBY_CLIENT.get_channels().await.map(|x| x.items)
+                } else {
+                    EMBY_CLIENT.get_latest(&id).await
+                }
+            },
+            glib::clone!(
+                #[weak]
+                hortu,
+                move |results: Vec<SimpleListItem>| {
+                    hortu.update_items(&results);
+                }
+            ),
+        )
+        .await
+        {
+            Ok(history) => history,
+            Err(e) => {
+                toast!(self, e.to_user_facing());
+                hortu.set_visible(false);
+                return;
+            }
+        };
+
+        hortu.set_items(&results);
     }
 
     pub async fn set_carousel(&self) {
@@ -144,6 +144,62 @@ impl HortuScrolled {
         imp.revealer.set_reveal_child(true);
     }
 
+    /// Replaces the items in place, keeping the objects at the unchanged head and tail of the list.
+    pub fn update_items(&self, items: &[SimpleListItem]) {
+        let store = self
+            .imp()
+            .selection
+            .model()
+            .unwrap()
+            .downcast::<gio::ListStore>()
+            .unwrap();
+
+        if store.n_items() == 0 || items.is_empty() {
+            self.set_items(items);
+            return;
+        }
+
+        let old_ids: Vec<String> = store
+            .iter::<TuObject>()
+            .flatten()
+            .map(|object| object.item().id())
+            .collect();
+
+        let prefix = old_ids
+            .iter()
+            .zip(items)
+            .take_while(|(old, new)| **old == new.id)
+            .count();
+        let suffix = old_ids[prefix..]
+            .iter()
+            .rev()
+            .zip(items[prefix..].iter().rev())
+            .take_while(|(old, new)| **old == new.id)
+            .count();
+
+        let removed = old_ids.len() - prefix - suffix;
+        let added: Vec<TuObject> = items[prefix..items.len() - suffix]
+            .iter()
+            .map(|result| {
+                let object = TuObject::from_simple(result, None);
+                object.item().set_is_resume(self.isresume());
+                object
+            })
+            .collect();
+
+        if removed == 0 && added.is_empty() {
+            return;
+        }
+
+        store.splice(prefix as u32, removed as u32, &added);
+    }
+
+    /// Shows the title with an empty row until the items are set.
+    pub fn set_placeholder(&self) {
+        self.set_visible(true);
+        self.imp().revealer.set_reveal_child(true);
+    }
+
     pub fn set_title(&self, title: &str) {
         self.imp().label.set_text(title);
     }
@@ -10,20 +10,28 @@ use crate::client::client::EMBY_CLIENT;
 use crate::client::error::UserFacingError;
 use crate::client::structs::*;
 use crate::toast;
+use crate::ui::models::texture_cache::TEXTURE_CACHE;
+use crate::ui::mpv::media_version::VersionPreference;
+use crate::ui::mpv::resume::start_percentage;
 
 use crate::ui::provider::dropdown_factory::{DropdownList, DropdownListBuilder};
 use crate::ui::provider::tu_item::TuItem;
 use crate::ui::provider::tu_object::TuObject;
-use crate::utils::{fetch_with_cache, get_image_with_cache, spawn, spawn_tokio, CachePolicy};
+use crate::ui::provider::{is_offline, subscribe_offline};
+use crate::utils::{
+    fetch_with_cache_and_refresh, get_image_with_cache, spawn, spawn_tokio, CachePolicy,
+};
 use chrono::{DateTime, Utc};
 
 use super::fix::ScrolledWindowFixExt;
 use super::hortu_scrolled::SHOW_BUTTON_ANIMATION_DURATION;
 use super::song_widget::format_duration;
+use super::subtitle_search_dialog::SubtitleSearchDialog;
 use super::tu_overview_item::run_time_ticks_to_label;
 use super::window::Window;
 
 pub(crate) mod imp {
+    use crate::client::structs::MediaSource;
     use crate::ui::provider::dropdown_factory::factory;
     use crate::ui::provider::tu_item::TuItem;
     use crate::ui::provider::tu_object::TuObject;
@@ -127,6 +135,7 @@ pub(crate) mod imp {
 
         pub videoselection: gtk::SingleSelection,
         pub subselection: gtk::SingleSelection,
+        pub media_sources: RefCell<Vec<MediaSource>>,
 
         #[template_child]
         pub main_carousel: TemplateChild<adw::Carousel>,
@@ -317,6 +326,23 @@ impl ItemPage {
         self.set_lists(&id).await;
     }
 
+    fn set_intro_when_online<const IS_VIDEO: bool>(&self, intro: &TuItem) {
+        let mut offline = subscribe_offline();
+        let obj = self.downgrade();
+        let intro = intro.clone();
+        spawn(async move {
+            while offline.changed().await.is_ok() {
+                if *offline.borrow_and_update() {
+                    continue;
+                }
+                if let Some(obj) = obj.upgrade() {
+                    obj.set_intro::<IS_VIDEO>(&intro).await;
+                }
+                break;
+            }
+        });
+    }
+
     async fn set_intro<const IS_VIDEO: bool>(&self, intro: &TuItem) {
         let intro_id = intro.id();
         let play_button = self.imp().playbutton.get();
@@ -325,6 +351,13 @@ impl ItemPage {
         self.set_now_item::<IS_VIDEO>(intro);
 
         play_button.set_sensitive(false);
+
+        // Playback needs the server, keep the play button disabled while offline
+        if is_offline() {
+            self.set_intro_when_online::<IS_VIDEO>(intro);
+            return;
+        }
+
         spinner.set_visible(true);
 
         let playback =
@@ -493,6 +526,7 @@ impl ItemPage {
         subdropdown.set_model(Some(&imp.subselection));
 
         let media_sources = playbackinfo.media_sources.clone();
+        imp.media_sources.replace(media_sources.clone());
 
         namedropdown.connect_selected_item_notify(move |dropdown| {
             let Some(entry) = dropdown
@@ -536,7 +570,7 @@ impl ItemPage {
             let Ok(dl) = DropdownListBuilder::default()
                 .line1(Some(media.name.clone()))
                 .line2(Some(media.container.clone()))
-                .direct_url(media.direct_stream_url.clone())
+                .direct_url(media.stream_url().map(ToOwned::to_owned))
                 .id(Some(media.id.clone()))
                 .build()
             else {
@@ -556,7 +590,13 @@ impl ItemPage {
         let file = gtk::gio::File::for_path(&path);
         let pathbuf = PathBuf::from(&path);
         if pathbuf.exists() {
-            backdrop.set_file(Some(&file));
+            let Some(texture) = TEXTURE_CACHE
+                .load(&format!("{}-Backdrop-0", id), pathbuf)
+                .await
+            else {
+                return;
+            };
+            backdrop.set_paintable(Some(&texture));
             self.imp()
                 .carousel
                 .imp()
@@ -581,15 +621,23 @@ impl ItemPage {
         let tags = image_tags.len();
         let carousel = imp.carousel.imp().carousel.get();
         for tag_num in 1..tags {
-            let path = get_image_with_cache(&id, "Backdrop", Some(tag_num as u8))
+            let path = get_image_with_cache(&id, "Backdrop", Some(tag_num as u32))
                 .await
                 .unwrap();
-            let file = gtk::gio::File::for_path(&path);
+            let Some(texture) = TEXTURE_CACHE
+                .load(
+                    &format!("{}-Backdrop-{}", id, tag_num),
+                    PathBuf::from(&path),
+                )
+                .await
+            else {
+                continue;
+            };
             let picture = gtk::Picture::builder()
                 .halign(gtk::Align::Fill)
                 .valign(gtk::Align::Fill)
                 .content_fit(gtk::ContentFit::Cover)
-                .file(&file)
+                .paintable(&texture)
                 .build();
             carousel.append(&picture);
             carousel.set_allow_scroll_wheel(true);
@@ -618,10 +666,29 @@ impl ItemPage {
             return;
         };
 
-        let season_list = match fetch_with_cache(
+        let season_list = match fetch_with_cache_and_refresh(
             &format!("season_{}", &id),
             CachePolicy::ReadCacheAndRefresh,
             async move { EMBY_CLIENT.get_season_list(&id).await },
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[weak]
+                season_list_store,
+                move |season_list: List| {
+                    let names: Vec<&str> =
+                        season_list.items.iter().map(|s| s.name.as_str()).collect();
+                    let old_names: Vec<String> = (0..season_list_store.n_items())
+                        .filter_map(|i| season_list_store.string(i))
+                        .map(|s| s.to_string())
+                        .collect();
+                    // Splicing resets the dropdown selection, so only do it when a season appeared or vanished
+                    if old_names != names {
+                        season_list_store.splice(0, season_list_store.n_items(), &names);
+                    }
+                    obj.imp().season_list_vec.replace(season_list.items);
+                }
+            ),
         )
         .await
         {
@@ -659,10 +726,21 @@ impl ItemPage {
     pub async fn set_overview(&self, id: &str) {
         let id = id.to_string();
 
-        let item = match fetch_with_cache(
+        let item = match fetch_with_cache_and_refresh(
             &format!("item_{}", &id),
             CachePolicy::ReadCacheAndRefresh,
             async move { EMBY_CLIENT.get_item_info(&id).await },
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |item: Item| {
+                    obj.set_overview_labels(&item);
+                    obj.set_user_data(&item);
+                    if let Some(actors) = item.people {
+                        obj.imp().actorhortu.update_items(&actors);
+                    }
+                }
+            ),
         )
         .await
         {
@@ -677,45 +755,7 @@ impl ItemPage {
             #[weak(rename_to = obj)]
             self,
             async move {
-                {
-                    let mut str = String::new();
-                    if let Some(communityrating) = item.community_rating {
-                        let formatted_rating = format!("{:.1}", communityrating);
-                        let crating = obj.imp().crating.get();
-                        crating.set_text(&formatted_rating);
-                        crating.set_visible(true);
-                        obj.imp().star.get().set_visible(true);
-                    }
-                    if let Some(rating) = item.official_rating {
-                        let orating = obj.imp().orating.get();
-                        orating.set_text(&rating);
-                        orating.set_visible(true);
-                    }
-                    if let Some(year) = item.production_year {
-                        str.push_str(&year.to_string());
-                        str.push_str("  ");
-                    }
-                    if let Some(runtime) = item.run_time_ticks {
-                        let time_string = run_time_ticks_to_label(runtime);
-                        str.push_str(&time_string);
-                        str.push_str("  ");
-                    }
-                    if let Some(genres) = &item.genres {
-                        for genre in genres {
-                            str.push_str(&genre.name);
-                            str.push(',');
-                        }
-                        str.pop();
-                    }
-                    obj.imp().line2.get().set_text(&str);
-
-                    if let Some(taglines) = item.taglines {
-                        if let Some(tagline) = taglines.first() {
-                            obj.imp().tagline.set_text(tagline);
-                            obj.imp().tagline.set_visible(true);
-                        }
-                    }
-                }
+                obj.set_overview_labels(&item);
                 if let Some(links) = item.external_urls {
                     obj.set_flowlinks(links);
                 }
@@ -734,18 +774,64 @@ impl ItemPage {
                 if let Some(image_tags) = item.backdrop_image_tags {
                     obj.add_backdrops(image_tags).await;
                 }
-                if let Some(ref user_data) = item.user_data {
-                    let imp = obj.imp();
-                    if let Some(is_favourite) = user_data.is_favorite {
-                        imp.actionbox.set_btn_active(is_favourite);
-                    }
-                    imp.actionbox.set_played(user_data.played);
-                    imp.actionbox.bind_edit();
-                }
+                obj.set_user_data(&item);
             }
         ));
     }
 
+    fn set_overview_labels(&self, item: &Item) {
+        let imp = self.imp();
+        let mut str = String::new();
+        if let Some(communityrating) = item.community_rating {
+            let formatted_rating = format!("{:.1}", communityrating);
+            let crating = imp.crating.get();
+            crating.set_text(&formatted_rating);
+            crating.set_visible(true);
+            imp.star.get().set_visible(true);
+        }
+        if let Some(rating) = &item.official_rating {
+            let orating = imp.orating.get();
+            orating.set_text(rating);
+            orating.set_visible(true);
+        }
+        if let Some(year) = item.production_year {
+            str.push_str(&year.to_string());
+            str.push_str("  ");
+        }
+        if let Some(runtime) = item.run_time_ticks {
+            let time_string = run_time_ticks_to_label(runtime);
+            str.push_str(&time_string);
+            str.push_str("  ");
+        }
+        if let Some(genres) = &item.genres {
+            for genre in genres {
+                str.push_str(&genre.name);
+                str.push(',');
+            }
+            str.pop();
+        }
+        imp.line2.get().set_text(&str);
+
+        if let Some(taglines) = &item.taglines {
+            if let Some(tagline) = taglines.first() {
+                imp.tagline.set_text(tagline);
+                imp.tagline.set_visible(true);
+            }
+        }
+    }
+
+    fn set_user_data(&self, item: &Item) {
+        let Some(user_data) = &item.user_data else {
+            return;
+        };
+        let imp = self.imp();
+        if let Some(is_favourite) = user_data.is_favorite {
+            imp.actionbox.set_btn_active(is_favourite);
+        }
+        imp.actionbox.set_played(user_data.played);
+        imp.actionbox.bind_edit();
+    }
+
     pub async fn createmediabox(
         &self,
         media_sources: Vec<MediaSource>,
@@ -909,7 +995,7 @@ impl ItemPage {
         let id = id.to_string();
         let types = types.to_string();
 
-        let results = match fetch_with_cache(
+        let results = match fetch_with_cache_and_refresh(
             &format!("item_{types}_{id}"),
             CachePolicy::ReadCacheAndRefresh,
             async move {
@@ -920,6 +1006,13 @@ impl ItemPage {
                     _ => Ok(List::default()),
                 }
             },
+            glib::clone!(
+                #[weak]
+                hortu,
+                move |results: List| {
+                    hortu.update_items(&results.items);
+                }
+            ),
         )
         .await
         {
@@ -1005,6 +1098,7 @@ impl ItemPage {
             playsessionid: self.play_session_id(),
             mediasourceid: media_source_id.to_string(),
             tick: 0,
+            state: PlaybackState::default(),
         };
 
         let sub_url = if let Some(sub_object) = sub_dropdown
@@ -1042,7 +1136,9 @@ impl ItemPage {
             None
         };
 
-        let percentage = item.played_percentage();
+        let Some(percentage) = start_percentage(self, &item).await else {
+            return;
+        };
 
         let episode_list = self.imp().episode_list_vec.borrow();
         let episode_list: Vec<TuItem> = episode_list
@@ -1050,8 +1146,19 @@ impl ItemPage {
             .map(|item| TuItem::from_simple(item, None))
             .collect();
 
-        self.get_window().play_media(
+        let play_method = self
+            .imp()
+            .media_sources
+            .borrow()
+            .iter()
+            .find(|media_source| &media_source.id == media_source_id)
+            .map(MediaSource::play_method)
+            .unwrap_or_default();
+
+        let window = self.get_window();
+        window.play_media(
             video_url.to_string(),
+            play_method,
             sub_url,
             item,
             episode_list,
@@ -1059,6 +1166,71 @@ impl ItemPage {
             None,
             percentage,
         );
+
+        // Following episodes are played in the version closest to this one
+        let media_sources = self.imp().media_sources.borrow().clone();
+        let mpvnav = &window.imp().mpvnav;
+        mpvnav.set_version_preference(
+            media_sources
+                .iter()
+                .find(|media_source| &media_source.id == media_source_id)
+                .map(VersionPreference::from_source),
+        );
+        mpvnav.set_media_sources(media_sources, media_source_id);
+    }
+
+    #[template_callback]
+    fn on_search_subtitles(&self) {
+        let Some(item) = self.current_item() else {
+            return;
+        };
+        let Some(video_object) = self
+            .imp()
+            .namedropdown
+            .selected_item()
+            .and_downcast::<glib::BoxedAnyObject>()
+        else {
+            return;
+        };
+        let video_dl: std::cell::Ref<DropdownList> = video_object.borrow();
+        let Some(ref media_source_id) = video_dl.id else {
+            return;
+        };
+
+        let dialog = SubtitleSearchDialog::new(&item.id(), media_source_id);
+        dialog.connect_downloaded(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                spawn(glib::clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        obj.refresh_subtitles().await;
+                    }
+                ));
+            }
+        ));
+        dialog.present(Some(self));
+    }
+
+    /// Reloads the version and subtitle lists after a subtitle was added on the server.
+    async fn refresh_subtitles(&self) {
+        let Some(item) = self.current_item() else {
+            return;
+        };
+        toast!(self, gettext("Subtitle downloaded"));
+        // The server lists the file once it has refreshed the item
+        glib::timeout_future_seconds(2).await;
+
+        let id = item.id();
+        match spawn_tokio(async move { EMBY_CLIENT.get_playbackinfo(&id).await }).await {
+            Ok(playback) => {
+                self.set_dropdown(&playback);
+                self.set_play_session_id(playback.play_session_id.clone());
+            }
+            Err(e) => toast!(self, e.to_user_facing()),
+        }
     }
 
     fn get_sub_url(media: &Media, media_source_id: &str, media_stream_id: &u64) -> Option<String> {
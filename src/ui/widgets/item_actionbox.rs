@@ -4,10 +4,11 @@ use adw::subclass::prelude::*;
 use gtk::{gio, prelude::*};
 use gtk::{glib, template_callbacks, CompositeTemplate};
 
-use crate::client::client::EMBY_CLIENT;
 use crate::client::error::UserFacingError;
+use crate::client::outbox::{Delivery, OutboxAction, OUTBOX};
 use crate::toast;
-use crate::utils::spawn_tokio;
+use crate::utils::{disable_while_offline, spawn_tokio, SERVER_ACTIONS};
+use gettextrs::gettext;
 
 use super::star_toggle::StarToggle;
 
@@ -87,15 +88,15 @@ impl ItemActionsBox {
         let id = self.id();
 
         if let Some(id) = id {
-            let result = if btn.is_active() {
-                spawn_tokio(async move { EMBY_CLIENT.like(&id).await }).await
+            let action = if btn.is_active() {
+                OutboxAction::Like(id)
             } else {
-                spawn_tokio(async move { EMBY_CLIENT.unlike(&id).await }).await
+                OutboxAction::Unlike(id)
             };
 
-            match result {
-                Ok(_) => {
-                    toast!(self, "Success");
+            match spawn_tokio(async move { OUTBOX.submit(action).await }).await {
+                Ok(delivery) => {
+                    self.toast_delivery(delivery);
                 }
                 Err(e) => {
                     toast!(self, e.to_user_facing());
@@ -104,8 +105,27 @@ impl ItemActionsBox {
         }
     }
 
+    fn toast_delivery(&self, delivery: Delivery) {
+        match delivery {
+            Delivery::Sent => toast!(self, gettext("Success")),
+            Delivery::Queued => {
+                toast!(
+                    self,
+                    gettext("Saved, will sync when the server is reachable")
+                );
+            }
+        }
+    }
+
     pub fn edit_metadata_action(&self) -> gio::SimpleActionGroup {
         let action_group = gio::SimpleActionGroup::new();
+        self.add_edit_actions(&action_group);
+        self.add_played_actions(&action_group);
+        disable_while_offline(&action_group, SERVER_ACTIONS);
+        action_group
+    }
+
+    fn add_edit_actions(&self, action_group: &gio::SimpleActionGroup) {
         action_group.add_action_entries([gio::ActionEntry::builder("editm")
             .activate(glib::clone!(
                 #[weak(rename_to = obj)]
@@ -136,6 +156,9 @@ impl ItemActionsBox {
                 }
             ))
             .build()]);
+    }
+
+    fn add_played_actions(&self, action_group: &gio::SimpleActionGroup) {
         if self.is_playable() {
             if self.played() {
                 action_group.add_action_entries([gio::ActionEntry::builder("unplayed")
@@ -150,13 +173,13 @@ impl ItemActionsBox {
                                     obj,
                                     async move {
                                         match spawn_tokio(async move {
-                                            EMBY_CLIENT.set_as_unplayed(&id).await
+                                            OUTBOX.submit(OutboxAction::Unplayed(id)).await
                                         })
                                         .await
                                         {
-                                            Ok(_) => {
+                                            Ok(delivery) => {
                                                 obj.set_played(false);
-                                                toast!(obj, "Success");
+                                                obj.toast_delivery(delivery);
                                                 obj.bind_edit();
                                             }
                                             Err(e) => {
@@ -182,13 +205,13 @@ impl ItemActionsBox {
                                     obj,
                                     async move {
                                         match spawn_tokio(async move {
-                                            EMBY_CLIENT.set_as_played(&id).await
+                                            OUTBOX.submit(OutboxAction::Played(id)).await
                                         })
                                         .await
                                         {
-                                            Ok(_) => {
+                                            Ok(delivery) => {
                                                 obj.set_played(true);
-                                                toast!(obj, "Success");
+                                                obj.toast_delivery(delivery);
                                                 obj.bind_edit();
                                             }
                                             Err(e) => {
@@ -203,7 +226,6 @@ impl ItemActionsBox {
                     .build()]);
             }
         }
-        action_group
     }
 
     pub fn bind_edit(&self) {
@@ -0,0 +1,332 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib::translate::IntoGlib;
+use gtk::glib::{self, closure_local};
+use gtk::template_callbacks;
+use tracing::warn;
+
+use crate::ui::mpv::keybindings::{BindingCommand, Keybinding, Keybindings};
+use crate::ui::mpv::tsukimi_mpv::{get_full_keystr, keystr_to_accelerator};
+use crate::utils::spawn;
+
+// Keys that only change the next key, a combination isn't done yet when they are pressed
+const MODIFIER_KEYS: &[gtk::gdk::Key] = &[
+    gtk::gdk::Key::Shift_L,
+    gtk::gdk::Key::Shift_R,
+    gtk::gdk::Key::Control_L,
+    gtk::gdk::Key::Control_R,
+    gtk::gdk::Key::Alt_L,
+    gtk::gdk::Key::Alt_R,
+    gtk::gdk::Key::Meta_L,
+    gtk::gdk::Key::Meta_R,
+    gtk::gdk::Key::Super_L,
+    gtk::gdk::Key::Super_R,
+    gtk::gdk::Key::ISO_Level3_Shift,
+    gtk::gdk::Key::Caps_Lock,
+];
+
+mod imp {
+    use super::*;
+
+    use glib::subclass::{InitializingObject, Signal};
+
+    use gtk::{glib, CompositeTemplate};
+    use once_cell::sync::Lazy;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/moe/tsukimi/keybindings_dialog.ui")]
+    pub struct KeybindingsDialog {
+        #[template_child]
+        pub toast: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub actions_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub commands_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub command_entry: TemplateChild<adw::EntryRow>,
+        pub keybindings: RefCell<Keybindings>,
+        pub rows: RefCell<Vec<(adw::PreferencesGroup, adw::ActionRow)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for KeybindingsDialog {
+        const NAME: &'static str = "KeybindingsDialog";
+        type Type = super::KeybindingsDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for KeybindingsDialog {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("changed").build()]);
+            SIGNALS.as_ref()
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.keybindings.replace(Keybindings::load());
+            self.obj().update_rows();
+        }
+    }
+
+    impl WidgetImpl for KeybindingsDialog {}
+    impl AdwDialogImpl for KeybindingsDialog {}
+}
+
+glib::wrapper! {
+    /// A dialog to change the keys of the player, its actions and mpv commands.
+    pub struct KeybindingsDialog(ObjectSubclass<imp::KeybindingsDialog>)
+        @extends gtk::Widget, adw::Dialog, @implements gtk::Accessible;
+}
+
+impl Default for KeybindingsDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[template_callbacks]
+impl KeybindingsDialog {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Called whenever the keybindings are saved.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            closure_local!(move |obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    fn update_rows(&self) {
+        let imp = self.imp();
+        for (group, row) in imp.rows.take() {
+            group.remove(&row);
+        }
+
+        let bindings = imp.keybindings.borrow().bindings().to_vec();
+        let mut rows = Vec::new();
+        for (index, binding) in bindings.iter().enumerate() {
+            let group = match binding.command {
+                BindingCommand::Action(_) => imp.actions_group.get(),
+                BindingCommand::Mpv(_) => imp.commands_group.get(),
+            };
+            let row = self.build_row(index, binding);
+            group.add(&row);
+            rows.push((group, row));
+        }
+        imp.rows.replace(rows);
+    }
+
+    fn build_row(&self, index: usize, binding: &Keybinding) -> adw::ActionRow {
+        let title = binding.command.title();
+        let row = adw::ActionRow::builder()
+            .title(&title)
+            .use_markup(false)
+            .activatable(true)
+            .build();
+        if let BindingCommand::Mpv(command) = &binding.command {
+            if *command != title {
+                row.set_subtitle(command);
+            }
+        }
+
+        let accelerator = binding
+            .key
+            .as_deref()
+            .and_then(keystr_to_accelerator)
+            .unwrap_or_default();
+        let label = gtk::ShortcutLabel::builder()
+            .accelerator(accelerator)
+            .disabled_text(gettext("Disabled"))
+            .valign(gtk::Align::Center)
+            .build();
+        row.add_suffix(&label);
+
+        // Actions can only lose their key, commands go away entirely
+        let (icon_name, tooltip) = match binding.command {
+            BindingCommand::Action(_) => ("edit-clear-symbolic", gettext("Disable Shortcut")),
+            BindingCommand::Mpv(_) => ("user-trash-symbolic", gettext("Remove Command")),
+        };
+        let button = gtk::Button::builder()
+            .icon_name(icon_name)
+            .tooltip_text(tooltip)
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .sensitive(binding.key.is_some() || matches!(binding.command, BindingCommand::Mpv(_)))
+            .build();
+        button.connect_clicked(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                obj.imp().keybindings.borrow_mut().remove(index);
+                obj.apply();
+            }
+        ));
+        row.add_suffix(&button);
+
+        row.connect_activated(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                spawn(glib::clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        obj.capture_key(index).await;
+                    }
+                ));
+            }
+        ));
+        row
+    }
+
+    /// Waits for a key combination and binds it to the command at `index`, asking first
+    /// if another command has it.
+    async fn capture_key(&self, index: usize) {
+        let Some(binding) = self
+            .imp()
+            .keybindings
+            .borrow()
+            .bindings()
+            .get(index)
+            .cloned()
+        else {
+            return;
+        };
+
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Press a Key Combination")),
+            Some(&binding.command.title()),
+        );
+        dialog.add_responses(&[("cancel", gettext("Cancel").as_str())]);
+        dialog.set_close_response("cancel");
+
+        let captured = Rc::new(RefCell::new(None::<String>));
+        let controller = gtk::EventControllerKey::new();
+        controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        controller.connect_key_pressed(glib::clone!(
+            #[weak]
+            dialog,
+            #[strong]
+            captured,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, key, _, state| {
+                // Escape leaves the dialog as usual
+                if key == gtk::gdk::Key::Escape || MODIFIER_KEYS.contains(&key) {
+                    return glib::Propagation::Proceed;
+                }
+                let Some(keystr) = get_full_keystr(key.into_glib(), state) else {
+                    return glib::Propagation::Proceed;
+                };
+                captured.replace(Some(keystr));
+                dialog.close();
+                glib::Propagation::Stop
+            }
+        ));
+        dialog.add_controller(controller);
+        dialog.choose_future(Some(self)).await;
+
+        let Some(key) = captured.take() else {
+            return;
+        };
+        let conflict = self
+            .imp()
+            .keybindings
+            .borrow()
+            .conflict(index, &key)
+            .cloned();
+        if let Some(other) = conflict {
+            if !self.confirm_replace(&other).await {
+                return;
+            }
+        }
+
+        self.imp()
+            .keybindings
+            .borrow_mut()
+            .set_key(index, Some(&key));
+        self.apply();
+    }
+
+    async fn confirm_replace(&self, other: &Keybinding) -> bool {
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Replace Shortcut?")),
+            Some(&format!(
+                "{} “{}”",
+                gettext("The shortcut is already used by"),
+                other.command.title()
+            )),
+        );
+        dialog.add_responses(&[
+            ("cancel", gettext("Cancel").as_str()),
+            ("replace", gettext("Replace").as_str()),
+        ]);
+        dialog.set_response_appearance("replace", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+        dialog.choose_future(Some(self)).await.as_str() == "replace"
+    }
+
+    /// Saves the keybindings and shows them.
+    fn apply(&self) {
+        if let Err(e) = self.imp().keybindings.borrow().save() {
+            warn!("Failed to save keybindings: {}", e);
+            self.show_toast(&gettext("Failed to save shortcuts"));
+            return;
+        }
+        self.update_rows();
+        self.emit_by_name::<()>("changed", &[]);
+    }
+
+    #[template_callback]
+    fn on_reset_clicked(&self) {
+        self.imp().keybindings.replace(Keybindings::defaults());
+        self.apply();
+        self.show_toast(&gettext("Shortcuts reset"));
+    }
+
+    // The overlay is inside the dialog, so `toast!` wouldn't find it
+    fn show_toast(&self, message: &str) {
+        self.imp().toast.add_toast(
+            adw::Toast::builder()
+                .timeout(2)
+                .use_markup(false)
+                .title(message)
+                .build(),
+        );
+    }
+
+    #[template_callback]
+    async fn on_command_apply(&self, entry: adw::EntryRow) {
+        let index = self
+            .imp()
+            .keybindings
+            .borrow_mut()
+            .add_command(&entry.text());
+        let Some(index) = index else {
+            return;
+        };
+        entry.set_text("");
+        self.apply();
+        self.capture_key(index).await;
+    }
+}
@@ -5,6 +5,7 @@ use gtk::subclass::prelude::*;
 use gtk::{gio, glib};
 
 use crate::client::client::EMBY_CLIENT;
+use crate::ui::mpv::video_queue::QueueSource;
 
 use super::single_grid::imp::ListType;
 use super::single_grid::SingleGrid;
@@ -114,6 +115,7 @@ impl ListPage {
             return;
         }
 
+        let has_videos = collection_type != "music";
         let include_item_types = get_include_item_types(collection_type);
 
         let pages = [
@@ -129,6 +131,9 @@ impl ListPage {
             let page = SingleGrid::new();
             page.set_list_type(list_type);
             page.handle_type();
+            if list_type == ListType::All && has_videos {
+                page.set_queue_source(QueueSource::Folder(id.clone()));
+            }
             let id_clone1 = id.clone();
             let include_item_types_clone1 = include_item_types.clone();
             page.connect_sort_changed_tokio(
@@ -1,11 +1,12 @@
 use crate::client::client::EMBY_CLIENT;
 use crate::ui::models::emby_cache_path;
+use crate::ui::models::texture_cache::TEXTURE_CACHE;
 use crate::utils::{spawn, spawn_tokio};
 use gtk::glib::{self, clone};
 use gtk::{prelude::*, Revealer};
 use tracing::{debug, warn};
 
-pub fn set_logo(id: String, image_type: &str, tag: Option<u8>) -> Revealer {
+pub fn set_logo(id: String, image_type: &str, tag: Option<u32>) -> Revealer {
     let image = gtk::Picture::new();
     image.set_halign(gtk::Align::Fill);
     image.set_content_fit(gtk::ContentFit::Contain);
@@ -18,19 +19,17 @@ pub fn set_logo(id: String, image_type: &str, tag: Option<u8>) -> Revealer {
         .build();
 
     let cache_path = emby_cache_path();
-    let path = format!("{}-{}-{}", id, image_type, tag.unwrap_or(0));
+    let key = format!("{}-{}-{}", id, image_type, tag.unwrap_or(0));
 
-    let id = id.to_string();
-
-    let pathbuf = cache_path.join(path);
-    if pathbuf.exists() {
-        if image.file().is_none() {
-            image.set_file(Some(&gtk::gio::File::for_path(pathbuf)));
-            revealer.set_reveal_child(true);
-        }
+    if let Some(texture) = TEXTURE_CACHE.get(&key) {
+        image.set_paintable(Some(&texture));
+        revealer.set_reveal_child(true);
         return revealer;
     }
 
+    let id = id.to_string();
+
+    let pathbuf = cache_path.join(&key);
     let image_type = image_type.to_string();
 
     spawn(clone!(
@@ -39,24 +38,28 @@ pub fn set_logo(id: String, image_type: &str, tag: Option<u8>) -> Revealer {
         #[weak]
         revealer,
         async move {
-            spawn_tokio(async move {
-                let mut retries = 0;
-                while retries < 3 {
-                    match EMBY_CLIENT.get_image(&id, &image_type, tag).await {
-                        Ok(_) => {
-                            break;
-                        }
-                        Err(e) => {
-                            warn!("Failed to get image: {}, retrying...", e);
-                            retries += 1;
+            if !pathbuf.exists() {
+                spawn_tokio(async move {
+                    let mut retries = 0;
+                    while retries < 3 {
+                        match EMBY_CLIENT.get_image(&id, &image_type, tag).await {
+                            Ok(_) => {
+                                break;
+                            }
+                            Err(e) => {
+                                warn!("Failed to get image: {}, retrying...", e);
+                                retries += 1;
+                            }
                         }
                     }
-                }
-            })
-            .await;
+                })
+                .await;
+            }
             debug!("Setting image: {}", &pathbuf.display());
-            let file = gtk::gio::File::for_path(pathbuf);
-            image.set_file(Some(&file));
+            let Some(texture) = TEXTURE_CACHE.load(&key, pathbuf).await else {
+                return;
+            };
+            image.set_paintable(Some(&texture));
             revealer.set_reveal_child(true);
         }
     ));
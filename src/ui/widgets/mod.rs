@@ -17,6 +17,7 @@ pub mod image_paintable;
 pub mod item;
 pub mod item_actionbox;
 pub mod item_carousel;
+pub mod keybindings_dialog;
 pub mod liked;
 pub mod list;
 pub mod list_dropdown;
@@ -36,6 +37,7 @@ pub mod single_grid;
 pub mod smooth_scale;
 pub mod song_widget;
 pub mod star_toggle;
+pub mod subtitle_search_dialog;
 pub mod tu_list_item;
 pub mod tu_overview_item;
 pub mod tuview_scrolled;
@@ -1,9 +1,10 @@
 use crate::client::client::EMBY_CLIENT;
 use crate::ui::models::emby_cache_path;
+use crate::ui::models::texture_cache::TEXTURE_CACHE;
 use crate::utils::{spawn, spawn_tokio};
 use adw::prelude::*;
 use adw::subclass::prelude::*;
-use gtk::gio;
+use gtk::gdk;
 use gtk::glib::{self, clone};
 use gtk::CompositeTemplate;
 use std::path::PathBuf;
@@ -77,6 +78,11 @@ impl PictureLoader {
     }
 
     pub fn load_pic(&self) {
+        if let Some(texture) = TEXTURE_CACHE.get(&self.cache_key()) {
+            self.set_texture(Some(texture));
+            return;
+        }
+
         let cache_file_path = self.cache_file();
 
         if cache_file_path.exists() {
@@ -87,13 +93,28 @@ impl PictureLoader {
     }
 
     pub fn reveal_picture(&self, cache_file_path: PathBuf) {
+        if !cache_file_path.exists() {
+            self.set_texture(None);
+            return;
+        }
+
+        let key = self.cache_key();
+        spawn(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let texture = TEXTURE_CACHE.load(&key, cache_file_path).await;
+                obj.set_texture(texture);
+            }
+        ));
+    }
+
+    fn set_texture(&self, texture: Option<gdk::Texture>) {
         let imp = self.imp();
 
-        if cache_file_path.exists() {
-            let file = gio::File::for_path(cache_file_path);
-            imp.picture.set_file(Some(&file));
-        } else {
-            imp.broken.set_visible(true);
+        match texture {
+            Some(texture) => imp.picture.set_paintable(Some(&texture)),
+            None => imp.broken.set_visible(true),
         }
 
         imp.spinner.set_visible(false);
@@ -101,15 +122,17 @@ impl PictureLoader {
         imp.revealer.set_reveal_child(true);
     }
 
-    pub fn cache_file(&self) -> PathBuf {
-        let cache_path = emby_cache_path();
-        let path = format!(
+    pub fn cache_key(&self) -> String {
+        format!(
             "{}-{}-{}",
             self.id(),
             self.imagetype(),
             self.tag().unwrap_or("0".to_string())
-        );
-        cache_path.join(path)
+        )
+    }
+
+    pub fn cache_file(&self) -> PathBuf {
+        emby_cache_path().join(self.cache_key())
     }
 
     pub fn get_file(&self, pathbuf: PathBuf) {
@@ -125,7 +148,7 @@ impl PictureLoader {
                     while retries < 3 {
                         let tag = tag.clone();
                         match EMBY_CLIENT
-                            .get_image(&id, &image_type, tag.and_then(|s| s.parse::<u8>().ok()))
+                            .get_image(&id, &image_type, tag.and_then(|s| s.parse::<u32>().ok()))
                             .await
                         {
                             Ok(_) => {
@@ -8,10 +8,7 @@ mod imp {
 
     use glib::subclass::InitializingObject;
 
-    use crate::{
-        client::client::EMBY_CLIENT,
-        ui::{models::SETTINGS, provider::account_item::AccountItem, widgets::window::Window},
-    };
+    use crate::ui::{provider::account_item::AccountItem, widgets::window::Window};
 
     use super::*;
 
@@ -55,10 +52,8 @@ mod imp {
     impl ListBoxRowImpl for ServerRow {
         fn activate(&self) {
             let account = self.obj().item().account();
-            SETTINGS.set_preferred_server(&account.servername).unwrap();
-            let _ = EMBY_CLIENT.init(&account);
             let window = self.obj().root().and_downcast::<Window>().unwrap();
-            window.reset();
+            window.switch_account(account);
         }
     }
 }
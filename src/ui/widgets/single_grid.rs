@@ -5,6 +5,7 @@ use super::utils::TuItemBuildExt;
 use crate::client::error::UserFacingError;
 use crate::client::structs::{List, SimpleListItem};
 use crate::ui::models::SETTINGS;
+use crate::ui::mpv::video_queue::QueueSource;
 use crate::utils::{spawn, spawn_tokio};
 use crate::{fraction, fraction_reset, toast};
 use adw::prelude::*;
@@ -26,6 +27,7 @@ pub mod imp {
     use gtk::{glib, CompositeTemplate};
 
     use crate::ui::models::SETTINGS;
+    use crate::ui::mpv::video_queue::QueueSource;
     use crate::ui::widgets::tu_list_item::imp::PosterType;
     use crate::ui::widgets::tuview_scrolled::TuViewScrolled;
 
@@ -141,6 +143,8 @@ pub mod imp {
         #[template_child]
         pub adbutton: TemplateChild<gtk::Box>,
         #[template_child]
+        pub queue_box: TemplateChild<gtk::Box>,
+        #[template_child]
         pub stack: TemplateChild<gtk::Stack>,
         #[template_child]
         pub scrolled: TemplateChild<TuViewScrolled>,
@@ -154,6 +158,8 @@ pub mod imp {
         #[property(get, set = Self::set_sort_by, builder(SortBy::default()))]
         pub sort_by: Cell<SortBy>,
         pub lock: Arc<AtomicBool>,
+        /// Where Play All and Shuffle take their videos from
+        pub queue_source: RefCell<Option<QueueSource>>,
     }
 
     // The central trait for subclassing a GObject
@@ -268,6 +274,33 @@ impl SingleGrid {
         let _ = SETTINGS.set_list_sort_by(dropdown.selected() as i32);
     }
 
+    #[template_callback]
+    async fn on_play_all_clicked(&self) {
+        self.play_queue(false).await;
+    }
+
+    #[template_callback]
+    async fn on_shuffle_clicked(&self) {
+        self.play_queue(true).await;
+    }
+
+    /// Offers to play the videos of `source` as a queue.
+    pub fn set_queue_source(&self, source: QueueSource) {
+        let imp = self.imp();
+        imp.queue_source.replace(Some(source));
+        imp.queue_box.set_visible(true);
+    }
+
+    async fn play_queue(&self, shuffle: bool) {
+        let Some(source) = self.imp().queue_source.borrow().clone() else {
+            return;
+        };
+        let Some(window) = self.root().and_downcast::<super::window::Window>() else {
+            return;
+        };
+        window.play_queue(source, shuffle).await;
+    }
+
     #[template_callback]
     fn filter_panel_cb(&self, _btn: &gtk::Button) {
         let dialog = adw::Dialog::builder()
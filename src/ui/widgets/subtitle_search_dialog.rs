@@ -0,0 +1,226 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib::{self, closure_local};
+use gtk::template_callbacks;
+
+use crate::client::structs::RemoteSubtitleInfo;
+use crate::ui::models::SETTINGS;
+use crate::ui::mpv::language_rules::canonical_language;
+use crate::utils::spawn;
+use crate::{
+    client::{client::EMBY_CLIENT, error::UserFacingError},
+    toast,
+    utils::spawn_tokio,
+};
+
+mod imp {
+    use super::*;
+
+    use glib::subclass::{InitializingObject, Signal};
+
+    use gtk::{glib, CompositeTemplate};
+    use once_cell::sync::Lazy;
+    use std::cell::OnceCell;
+
+    #[derive(Debug, Default, CompositeTemplate, glib::Properties)]
+    #[template(resource = "/moe/tsukimi/subtitle_search_dialog.ui")]
+    #[properties(wrapper_type = super::SubtitleSearchDialog)]
+    pub struct SubtitleSearchDialog {
+        #[property(get, set, construct_only)]
+        pub id: OnceCell<String>,
+        #[property(get, set, construct_only)]
+        pub media_source_id: OnceCell<String>,
+        #[template_child]
+        pub language_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub result_list: TemplateChild<gtk::ListBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SubtitleSearchDialog {
+        const NAME: &'static str = "SubtitleSearchDialog";
+        type Type = super::SubtitleSearchDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for SubtitleSearchDialog {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("downloaded").build()]);
+            SIGNALS.as_ref()
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().init();
+        }
+    }
+
+    impl WidgetImpl for SubtitleSearchDialog {}
+    impl AdwDialogImpl for SubtitleSearchDialog {}
+}
+
+glib::wrapper! {
+    /// A dialog to search subtitles with the server's subtitle providers and download one.
+    pub struct SubtitleSearchDialog(ObjectSubclass<imp::SubtitleSearchDialog>)
+        @extends gtk::Widget, adw::Dialog, @implements gtk::Accessible;
+}
+
+#[template_callbacks]
+impl SubtitleSearchDialog {
+    pub fn new(id: &str, media_source_id: &str) -> Self {
+        glib::Object::builder()
+            .property("id", id)
+            .property("media-source-id", media_source_id)
+            .build()
+    }
+
+    pub fn connect_downloaded<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "downloaded",
+            true,
+            closure_local!(move |obj: Self| {
+                f(&obj);
+            }),
+        )
+    }
+
+    fn init(&self) {
+        let language = SETTINGS
+            .mpv_subtitle_lang_priority()
+            .first()
+            .map(|lang| canonical_language(lang))
+            .unwrap_or_else(|| "eng".to_string());
+        self.imp().language_entry.set_text(&language);
+
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move { obj.on_search().await }
+        ));
+    }
+
+    #[template_callback]
+    async fn on_search(&self) {
+        let imp = self.imp();
+        let language = canonical_language(&imp.language_entry.text());
+        if language.is_empty() {
+            return;
+        }
+
+        imp.stack.set_visible_child_name("loading");
+
+        let id = self.id();
+        let media_source_id = self.media_source_id();
+        match spawn_tokio(async move {
+            EMBY_CLIENT
+                .search_remote_subtitles(&id, &language, &media_source_id)
+                .await
+        })
+        .await
+        {
+            Ok(results) if results.is_empty() => {
+                imp.stack.set_visible_child_name("empty");
+            }
+            Ok(results) => {
+                imp.stack.set_visible_child_name("result");
+                self.load_results(results);
+            }
+            Err(e) => {
+                imp.stack.set_visible_child_name("empty");
+                toast!(imp.stack, e.to_user_facing());
+            }
+        }
+    }
+
+    fn load_results(&self, results: Vec<RemoteSubtitleInfo>) {
+        let result_list = &self.imp().result_list;
+        result_list.remove_all();
+
+        for result in results {
+            let mut details = Vec::new();
+            details.extend(result.provider_name.clone());
+            details.extend(result.format.as_ref().map(|format| format.to_uppercase()));
+            details.extend(
+                result
+                    .community_rating
+                    .map(|rating| format!("★ {:.1}", rating)),
+            );
+            details.extend(
+                result
+                    .download_count
+                    .map(|count| format!("{} {}", count, gettext("downloads"))),
+            );
+            if result.is_hash_match == Some(true) {
+                details.push(gettext("Matches this file"));
+            }
+
+            let row = adw::ActionRow::builder()
+                .title(&result.name)
+                .subtitle(details.join(" · "))
+                .use_markup(false)
+                .build();
+            let button = gtk::Button::builder()
+                .icon_name("folder-download-symbolic")
+                .tooltip_text(gettext("Download"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            button.connect_clicked(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |button| {
+                    let subtitle_id = result.id.clone();
+                    spawn(glib::clone!(
+                        #[weak]
+                        obj,
+                        #[weak]
+                        button,
+                        async move {
+                            obj.download(&subtitle_id, &button).await;
+                        }
+                    ));
+                }
+            ));
+            row.add_suffix(&button);
+            row.set_activatable_widget(Some(&button));
+            result_list.append(&row);
+        }
+    }
+
+    async fn download(&self, subtitle_id: &str, button: &gtk::Button) {
+        button.set_sensitive(false);
+
+        let id = self.id();
+        let subtitle_id = subtitle_id.to_string();
+        match spawn_tokio(async move {
+            EMBY_CLIENT
+                .download_remote_subtitle(&id, &subtitle_id)
+                .await
+        })
+        .await
+        {
+            Ok(_) => {
+                self.emit_by_name::<()>("downloaded", &[]);
+                self.close();
+            }
+            Err(e) => {
+                toast!(self.imp().stack, e.to_user_facing());
+                button.set_sensitive(true);
+            }
+        }
+    }
+}
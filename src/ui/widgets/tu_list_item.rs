@@ -14,11 +14,13 @@ use tracing::warn;
 
 use crate::client::client::EMBY_CLIENT;
 use crate::client::error::UserFacingError;
+use crate::client::outbox::{Delivery, OutboxAction, OUTBOX};
 use crate::toast;
 use crate::ui::provider::tu_item::TuItem;
 use crate::ui::provider::IS_ADMIN;
 use crate::utils::spawn;
 use crate::utils::spawn_tokio;
+use crate::utils::{disable_while_offline, SERVER_ACTIONS};
 use anyhow::Result;
 
 use super::picture_loader::PictureLoader;
@@ -682,17 +684,21 @@ impl TuListItem {
                 ))
                 .build()]);
         }
+        // Left in the menu while offline, but disabled
+        disable_while_offline(&action_group, SERVER_ACTIONS);
         Some(action_group)
     }
 
-    async fn perform_action_inner(id: &str, action: &Action) -> Result<()> {
-        match action {
-            Action::Like => EMBY_CLIENT.like(id).await,
-            Action::Unlike => EMBY_CLIENT.unlike(id).await,
-            Action::Played => EMBY_CLIENT.set_as_played(id).await,
-            Action::Unplayed => EMBY_CLIENT.set_as_unplayed(id).await,
-            Action::Remove => EMBY_CLIENT.hide_from_resume(id).await,
-        }
+    async fn perform_action_inner(id: &str, action: &Action) -> Result<Delivery> {
+        let id = id.to_string();
+        let action = match action {
+            Action::Like => OutboxAction::Like(id),
+            Action::Unlike => OutboxAction::Unlike(id),
+            Action::Played => OutboxAction::Played(id),
+            Action::Unplayed => OutboxAction::Unplayed(id),
+            Action::Remove => OutboxAction::HideFromResume(id),
+        };
+        OUTBOX.submit(action).await
     }
 
     pub async fn perform_action(&self, action: Action) {
@@ -701,9 +707,15 @@ impl TuListItem {
         let result = spawn_tokio(async move { Self::perform_action_inner(&id, &action).await });
 
         match result.await {
-            Ok(_) => {
+            Ok(Delivery::Sent) => {
                 toast!(self, gettext("Success"))
             }
+            Ok(Delivery::Queued) => {
+                toast!(
+                    self,
+                    gettext("Saved, will sync when the server is reachable")
+                );
+            }
             Err(e) => {
                 toast!(self, e.to_user_facing());
             }
@@ -18,6 +18,7 @@ use crate::ui::provider::tu_item::TuItem;
 use crate::ui::provider::IS_ADMIN;
 use crate::utils::spawn;
 use crate::utils::spawn_tokio;
+use crate::utils::{disable_while_offline, SERVER_ACTIONS};
 
 use super::picture_loader::PictureLoader;
 
@@ -507,6 +508,8 @@ impl TuOverviewItem {
                 ))
                 .build()]);
         }
+        // Left in the menu while offline, but disabled
+        disable_while_offline(&action_group, SERVER_ACTIONS);
         Some(action_group)
     }
 
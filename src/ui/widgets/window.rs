@@ -6,7 +6,7 @@ use gtk::subclass::prelude::*;
 use gtk::ListBoxRow;
 use gtk::Widget;
 mod imp {
-    use std::cell::{OnceCell, RefCell};
+    use std::cell::{Cell, OnceCell, RefCell};
 
     use adw::subclass::application_window::AdwApplicationWindowImpl;
     use glib::subclass::InitializingObject;
@@ -84,6 +84,10 @@ mod imp {
         pub searchpage: TemplateChild<adw::Bin>,
         #[template_child]
         pub mpv_playlist: TemplateChild<gtk::ListView>,
+        #[template_child]
+        pub mpv_version_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub offline_banner: TemplateChild<adw::Banner>,
 
         pub progress_bar_animation: OnceCell<adw::TimedAnimation>,
         pub progress_bar_fade_animation: OnceCell<adw::TimedAnimation>,
@@ -91,6 +95,10 @@ mod imp {
         pub last_content_list_selection: RefCell<Option<i32>>,
 
         pub mpv_playlist_selection: gtk::SingleSelection,
+        pub mpv_version_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        pub outbox_retry: RefCell<Option<glib::SourceId>>,
+        pub outbox_retry_delay: Cell<u32>,
     }
 
     // The central trait for subclassing a GObject
@@ -169,6 +177,10 @@ mod imp {
             obj.set_servers();
             obj.set_nav_servers();
             obj.set_shortcuts();
+            obj.set_offline_action();
+            obj.watch_network_metered();
+            obj.watch_network_changed();
+            obj.set_version_picker();
         }
     }
 
@@ -179,10 +191,22 @@ mod imp {
     impl WindowImpl for Window {
         // Save window state right before the window will be closed
         fn close_request(&self) -> glib::Propagation {
+            let obj = self.obj();
             // Save window size
-            self.obj()
-                .save_window_size()
-                .expect("Failed to save window state");
+            obj.save_window_size().expect("Failed to save window state");
+            // Keep the app alive until the server knows where playback stopped
+            if self.mpvnav.is_reporting() {
+                obj.set_visible(false);
+                crate::utils::spawn(glib::clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        obj.imp().mpvnav.shutdown().await;
+                        obj.close();
+                    }
+                ));
+                return glib::Propagation::Stop;
+            }
             // Allow to invoke other event handlers
             glib::Propagation::Proceed
         }
@@ -194,16 +218,24 @@ mod imp {
 }
 
 use crate::client::client::EMBY_CLIENT;
-use crate::client::structs::Back;
+use crate::client::error::UserFacingError;
+use crate::client::outbox::OUTBOX;
+use crate::client::structs::{Back, PlayMethod};
 use crate::config::load_cfgv2;
 use crate::config::Account;
+use crate::toast;
 use crate::ui::models::SETTINGS;
+use crate::ui::mpv::keybindings::{BindingGroup, Keybindings};
+use crate::ui::mpv::resume::start_percentage;
+use crate::ui::mpv::tsukimi_mpv::keystr_to_accelerator;
+use crate::ui::mpv::video_queue::{fetch_queue, QueueSource};
 use crate::ui::provider::core_song::CoreSong;
 use crate::ui::provider::tu_item::TuItem;
 use crate::ui::provider::tu_object::TuObject;
-use crate::ui::provider::IS_ADMIN;
-use crate::utils::spawn;
+use crate::ui::provider::{is_offline, set_metered, IS_ADMIN};
+use crate::utils::{spawn, spawn_tokio};
 use crate::APP_ID;
+use gettextrs::gettext;
 use glib::Object;
 use gtk::{gio, glib, template_callbacks};
 
@@ -222,6 +254,8 @@ glib::wrapper! {
 }
 
 pub const PROGRESSBAR_FADE_ANIMATION_DURATION: u32 = 500;
+const OUTBOX_RETRY_MIN_SECS: u32 = 15;
+const OUTBOX_RETRY_MAX_SECS: u32 = 10 * 60;
 
 #[template_callbacks]
 impl Window {
@@ -306,13 +340,11 @@ impl Window {
             #[weak(rename_to = obj)]
             self,
             move |_, row| {
-                unsafe {
+                let account = unsafe {
                     let account_ptr: std::ptr::NonNull<Account> = row.data("account").unwrap();
-                    let account: &Account = &*account_ptr.as_ptr();
-                    let _ = EMBY_CLIENT.init(account);
-                    SETTINGS.set_preferred_server(&account.servername).unwrap();
-                }
-                obj.reset();
+                    account_ptr.as_ref().clone()
+                };
+                obj.switch_account(account);
             }
         ));
     }
@@ -332,6 +364,20 @@ impl Window {
         account_row.activate();
     }
 
+    /// Switches to another account, reporting any playback to the old server first.
+    pub fn switch_account(&self, account: Account) {
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                obj.imp().mpvnav.shutdown().await;
+                SETTINGS.set_preferred_server(&account.servername).unwrap();
+                let _ = EMBY_CLIENT.init(&account);
+                obj.reset();
+            }
+        ));
+    }
+
     pub fn reset(&self) {
         self.mainpage();
         self.imp().selectlist.unselect_all();
@@ -340,6 +386,174 @@ impl Window {
         self.homepage();
     }
 
+    /// Keeps track of metered connections, which have their own streaming bitrate limit.
+    fn watch_network_metered(&self) {
+        let monitor = gio::NetworkMonitor::default();
+        set_metered(monitor.is_network_metered());
+        monitor.connect_network_metered_notify(|monitor| {
+            set_metered(monitor.is_network_metered());
+        });
+    }
+
+    /// Sends the queued changes as soon as the network comes back.
+    fn watch_network_changed(&self) {
+        let monitor = gio::NetworkMonitor::default();
+        monitor.connect_network_changed(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_, available| {
+                if !available || is_offline() || OUTBOX.pending_count() == 0 {
+                    return;
+                }
+                obj.cancel_outbox_retry();
+                spawn(async move {
+                    let _ = obj.flush_outbox().await;
+                });
+            }
+        ));
+    }
+
+    fn set_offline_action(&self) {
+        let action_offline = gio::ActionEntry::builder("offline")
+            .state(false.to_variant())
+            .activate(|window: &Self, action, _| {
+                let offline = action
+                    .state()
+                    .and_then(|state| state.get::<bool>())
+                    .unwrap_or_default();
+                window.set_offline(!offline);
+            })
+            .build();
+        self.add_action_entries([action_offline]);
+
+        let mut pending = OUTBOX.subscribe();
+        let obj = self.downgrade();
+        spawn(async move {
+            while pending.changed().await.is_ok() {
+                let Some(obj) = obj.upgrade() else {
+                    break;
+                };
+                obj.update_offline_banner();
+                if OUTBOX.pending_count() == 0 {
+                    obj.cancel_outbox_retry();
+                } else {
+                    obj.schedule_outbox_retry();
+                }
+            }
+        });
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        crate::ui::provider::set_offline(offline);
+        if let Some(action) = self
+            .lookup_action("offline")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_state(&offline.to_variant());
+        }
+        self.update_offline_banner();
+
+        self.cancel_outbox_retry();
+        if offline {
+            return;
+        }
+
+        spawn(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                if let Err(e) = obj.flush_outbox().await {
+                    toast!(obj, e.to_user_facing());
+                }
+                obj.on_home_update();
+            }
+        ));
+    }
+
+    /// Sends the queued changes. While the server can't be reached they are retried
+    /// later, waiting twice as long after every failed attempt.
+    async fn flush_outbox(&self) -> anyhow::Result<()> {
+        match spawn_tokio(async { OUTBOX.flush().await }).await {
+            Ok(synced) => {
+                self.imp().outbox_retry_delay.set(0);
+                if synced > 0 {
+                    toast!(self, format!("{}: {}", gettext("Synced changes"), synced));
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.schedule_outbox_retry();
+                Err(e)
+            }
+        }
+    }
+
+    fn schedule_outbox_retry(&self) {
+        let imp = self.imp();
+        if imp.outbox_retry.borrow().is_some() || is_offline() || OUTBOX.pending_count() == 0 {
+            return;
+        }
+        let delay = imp.outbox_retry_delay.get().max(OUTBOX_RETRY_MIN_SECS);
+        let source = glib::timeout_add_seconds_local_once(
+            delay,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    let imp = obj.imp();
+                    imp.outbox_retry.take();
+                    imp.outbox_retry_delay
+                        .set((delay * 2).min(OUTBOX_RETRY_MAX_SECS));
+                    spawn(async move {
+                        let _ = obj.flush_outbox().await;
+                    });
+                }
+            ),
+        );
+        imp.outbox_retry.replace(Some(source));
+    }
+
+    fn cancel_outbox_retry(&self) {
+        let imp = self.imp();
+        if let Some(source) = imp.outbox_retry.take() {
+            source.remove();
+        }
+        imp.outbox_retry_delay.set(0);
+    }
+
+    /// Shows the offline banner, or how many changes are still waiting to be synced.
+    pub fn update_offline_banner(&self) {
+        let banner = &self.imp().offline_banner;
+        let pending = OUTBOX.pending_count();
+        banner.set_revealed(is_offline() || pending > 0);
+
+        let title = if is_offline() {
+            gettext("Offline, showing cached data")
+        } else {
+            gettext("Server unreachable")
+        };
+        if pending == 0 {
+            banner.set_title(&title);
+        } else {
+            banner.set_title(&format!(
+                "{}, {}: {}",
+                title,
+                gettext("Pending changes"),
+                pending
+            ));
+        }
+        banner.set_button_label(Some(&if is_offline() {
+            gettext("Go Online")
+        } else {
+            gettext("Retry")
+        }));
+    }
+
+    #[template_callback]
+    fn on_go_online(&self) {
+        self.set_offline(false);
+    }
+
     pub fn hard_set_fraction(&self, to_value: f64) {
         let progressbar = &self.imp().progressbar;
         self.progressbar_animation().pause();
@@ -661,6 +875,7 @@ impl Window {
     pub fn play_media(
         &self,
         url: String,
+        play_method: PlayMethod,
         suburl: Option<String>,
         item: TuItem,
         episode_list: Vec<TuItem>,
@@ -673,6 +888,7 @@ impl Window {
         self.set_mpv_playlist(&episode_list);
         imp.mpvnav.play(
             &url,
+            play_method,
             suburl.as_deref(),
             item,
             episode_list,
@@ -681,6 +897,30 @@ impl Window {
         );
     }
 
+    /// Fetches the videos of `source` and plays them with the mpv playlist as the queue.
+    pub async fn play_queue(&self, source: QueueSource, shuffle: bool) {
+        toast!(self, gettext("Loading Videos..."));
+        let queue = match fetch_queue(source, shuffle).await {
+            Ok(queue) => queue,
+            Err(e) => {
+                toast!(self, e.to_user_facing());
+                return;
+            }
+        };
+        let Some(item) = queue.first().cloned() else {
+            toast!(self, gettext("No videos found"));
+            return;
+        };
+        let Some(percentage) = start_percentage(self, &item).await else {
+            return;
+        };
+
+        let imp = self.imp();
+        imp.stack.set_visible_child_name("mpv");
+        self.set_mpv_playlist(&queue);
+        imp.mpvnav.play_queue(item, queue, percentage).await;
+    }
+
     pub fn push_page<T>(&self, page: &T)
     where
         T: NavigationPageExt,
@@ -787,16 +1027,50 @@ impl Window {
         }
     }
 
+    /// Builds the shortcuts window of the player from the keybindings in use.
     pub fn set_shortcuts(&self) {
-        let Some(window) = gtk::Builder::from_resource("/moe/tsukimi/mpv_shortcuts_window.ui")
-            .object::<gtk::ShortcutsWindow>("mpv_shortcuts")
-        else {
-            eprintln!("Failed to load shortcuts window");
-            return;
-        };
+        let keybindings = Keybindings::load();
+        let section = gtk::ShortcutsSection::builder()
+            .section_name("shortcuts")
+            .max_height(10)
+            .build();
+        for group in BindingGroup::ALL {
+            let shortcuts = keybindings
+                .bindings()
+                .iter()
+                .filter(|binding| binding.command.group() == group)
+                .filter_map(|binding| {
+                    let accelerator = keystr_to_accelerator(binding.key.as_deref()?)?;
+                    Some(
+                        gtk::ShortcutsShortcut::builder()
+                            .accelerator(accelerator)
+                            .title(binding.command.title())
+                            .build(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            if shortcuts.is_empty() {
+                continue;
+            }
+
+            let shortcuts_group = gtk::ShortcutsGroup::builder().title(group.title()).build();
+            for shortcut in &shortcuts {
+                shortcuts_group.add_shortcut(shortcut);
+            }
+            section.add_group(&shortcuts_group);
+        }
+
+        let window = gtk::ShortcutsWindow::builder().modal(true).build();
+        window.add_section(&section);
         self.set_help_overlay(Some(&window));
     }
 
+    /// Applies edited keybindings to the player and its shortcuts window.
+    pub fn reload_keybindings(&self) {
+        self.imp().mpvnav.reload_keybindings();
+        self.set_shortcuts();
+    }
+
     pub fn set_mpv_playlist(&self, episode_list: &Vec<TuItem>) {
         let imp = self.imp();
         let model = imp.mpv_playlist_selection.model();
@@ -812,6 +1086,49 @@ impl Window {
         }
     }
 
+    fn set_version_picker(&self) {
+        let imp = self.imp();
+        let handler = imp
+            .mpv_version_dropdown
+            .connect_selected_notify(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |dropdown| {
+                    let index = dropdown.selected();
+                    crate::utils::spawn(glib::clone!(
+                        #[weak]
+                        obj,
+                        async move {
+                            obj.imp().mpvnav.select_version(index).await;
+                        }
+                    ));
+                }
+            ));
+        imp.mpv_version_handler.replace(Some(handler));
+    }
+
+    /// Lists the versions of the playing video in the playlist sidebar. The picker is
+    /// hidden when there is nothing to choose from.
+    pub fn set_mpv_versions(&self, names: &[String], selected: u32) {
+        let imp = self.imp();
+        let dropdown = &imp.mpv_version_dropdown;
+        let Some(model) = dropdown.model().and_downcast::<gtk::StringList>() else {
+            return;
+        };
+
+        let handler = imp.mpv_version_handler.borrow();
+        if let Some(handler) = handler.as_ref() {
+            dropdown.block_signal(handler);
+        }
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        model.splice(0, model.n_items(), &names);
+        dropdown.set_selected(selected);
+        dropdown.set_visible(names.len() > 1);
+        if let Some(handler) = handler.as_ref() {
+            dropdown.unblock_signal(handler);
+        }
+    }
+
     #[template_callback]
     async fn on_playlist_item_activated(&self, position: u32, view: &gtk::ListView) {
         let Some(model) = view.model() else {
@@ -1,9 +1,12 @@
 use std::future::Future;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
 use crate::client::{client::EMBY_CLIENT, network::runtime};
 use crate::ui::models::emby_cache_path;
-use anyhow::Result;
+use crate::ui::provider::{is_offline, subscribe_offline};
+use anyhow::{anyhow, Result};
+use gettextrs::gettext;
 use serde::{Deserialize, Serialize};
 
 pub fn _spawn_tokio_blocking<F>(fut: F) -> F::Output
@@ -55,6 +58,38 @@ where
     });
 }
 
+/// Actions of item menus that need the server, as opposed to those the outbox can queue.
+pub const SERVER_ACTIONS: &[&str] = &["editm", "editi", "scan", "identify", "refresh"];
+
+/// Keeps the actions of `group` named in `names` disabled while offline, following offline
+/// mode as it's turned on and off.
+pub fn disable_while_offline(group: &gtk::gio::SimpleActionGroup, names: &'static [&str]) {
+    use gtk::prelude::*;
+
+    let set_enabled = |group: &gtk::gio::SimpleActionGroup, offline: bool| {
+        for name in names {
+            if let Some(action) = group
+                .lookup_action(name)
+                .and_downcast::<gtk::gio::SimpleAction>()
+            {
+                action.set_enabled(!offline);
+            }
+        }
+    };
+    set_enabled(group, is_offline());
+
+    let mut offline = subscribe_offline();
+    let group = group.downgrade();
+    spawn(async move {
+        while offline.changed().await.is_ok() {
+            let Some(group) = group.upgrade() else {
+                break;
+            };
+            set_enabled(&group, *offline.borrow_and_update());
+        }
+    });
+}
+
 pub enum CachePolicy {
     UseCacheIfAvailable,
     RefreshCache,
@@ -71,10 +106,32 @@ pub async fn fetch_with_cache<T, F>(
 where
     T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
     F: Future<Output = Result<T>> + Send + 'static,
+{
+    fetch_with_cache_and_refresh(cache_key, cache_policy, future, |_| {}).await
+}
+
+/// Like [`fetch_with_cache`], but when a cached value is returned and refreshed in the
+/// background, `on_refresh` is called on the main thread with the fresh value if it differs.
+pub async fn fetch_with_cache_and_refresh<T, F, C>(
+    cache_key: &str,
+    cache_policy: CachePolicy,
+    future: F,
+    on_refresh: C,
+) -> Result<T>
+where
+    T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+    F: Future<Output = Result<T>> + Send + 'static,
+    C: FnOnce(T) + 'static,
 {
     let mut path = emby_cache_path();
     path.push(format!("{}.json", cache_key));
 
+    if is_offline() {
+        return read_from_cache(&path)
+            .map(|(data, _)| data)
+            .ok_or_else(|| anyhow!(gettext("Not available offline")));
+    }
+
     let read_cache = matches!(
         cache_policy,
         CachePolicy::UseCacheIfAvailable | CachePolicy::ReadCacheAndRefresh
@@ -88,14 +145,22 @@ where
     let update_in_background = matches!(cache_policy, CachePolicy::ReadCacheAndRefresh);
 
     if read_cache {
-        if let Some(data) = read_from_cache(&path) {
-            if update_in_background {
-                let future = future;
+        if let Some((data, contents)) = read_from_cache(&path) {
+            if update_in_background && !is_cache_fresh(cache_key, &path) {
                 let path = path.clone();
-                runtime().spawn(async move {
-                    if let Ok(data) = future.await {
-                        let _ = write_to_cache(&path, &data);
+                spawn(async move {
+                    let Ok(data) = spawn_tokio(future).await else {
+                        return;
+                    };
+                    let Ok(serialized) = serde_json::to_string(&data) else {
+                        return;
+                    };
+                    if serialized == contents {
+                        let _ = touch_cache(&path);
+                        return;
                     }
+                    let _ = std::fs::write(&path, serialized);
+                    on_refresh(data);
                 });
             }
             return Ok(data);
@@ -111,13 +176,40 @@ where
     Ok(data)
 }
 
-fn read_from_cache<T>(path: &PathBuf) -> Option<T>
+/// How long a cached response is considered fresh enough to skip the background refresh.
+fn cache_ttl(cache_key: &str) -> Duration {
+    match cache_key {
+        // The library list rarely changes, everything else carries the played state and
+        // resume position of its items
+        "library" => Duration::from_secs(60 * 60),
+        _ => Duration::ZERO,
+    }
+}
+
+fn is_cache_fresh(cache_key: &str, path: &PathBuf) -> bool {
+    let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+        return false;
+    };
+    modified
+        .elapsed()
+        .is_ok_and(|elapsed| elapsed < cache_ttl(cache_key))
+}
+
+fn touch_cache(path: &PathBuf) -> Result<()> {
+    std::fs::File::options()
+        .append(true)
+        .open(path)?
+        .set_modified(SystemTime::now())?;
+    Ok(())
+}
+
+fn read_from_cache<T>(path: &PathBuf) -> Option<(T, String)>
 where
     T: for<'de> Deserialize<'de>,
 {
-    std::fs::read_to_string(path)
-        .ok()
-        .and_then(|contents| serde_json::from_str(&contents).ok())
+    let contents = std::fs::read_to_string(path).ok()?;
+    let data = serde_json::from_str(&contents).ok()?;
+    Some((data, contents))
 }
 
 fn write_to_cache<T>(path: &PathBuf, data: &T) -> Result<()>
@@ -129,7 +221,7 @@ where
     Ok(())
 }
 
-pub async fn get_image_with_cache(id: &str, img_type: &str, tag: Option<u8>) -> Result<String> {
+pub async fn get_image_with_cache(id: &str, img_type: &str, tag: Option<u32>) -> Result<String> {
     let mut path = emby_cache_path();
     path.push(format!("{}-{}-{}", id, img_type, tag.unwrap_or(0)));
 